@@ -0,0 +1,281 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error, Result, anyhow};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use tempfile::NamedTempFile;
+use walkdir::WalkDir;
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    TarZst,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detects the archive format from the file extension of the given path or URL
+    pub fn detect(location: impl AsRef<str>) -> Option<ArchiveFormat> {
+        // Query and fragment parts of a URL are not part of the file name
+        let location = location.as_ref();
+        let path = location
+            .split(['?', '#'])
+            .next()
+            .unwrap_or(location)
+            .to_lowercase();
+
+        if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if path.ends_with(".tar.zst") || path.ends_with(".tzst") {
+            Some(ArchiveFormat::TarZst)
+        } else if path.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Checks whether the given location is an HTTP(S) URL
+pub fn is_remote(location: impl AsRef<str>) -> bool {
+    let location = location.as_ref();
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Returns the local path of a `file://` URL or a plain path
+pub fn local_path(location: impl AsRef<str>) -> PathBuf {
+    let location = location.as_ref();
+    PathBuf::from(location.strip_prefix("file://").unwrap_or(location))
+}
+
+/// Downloads (if needed) and unpacks the archive at the given location into the output directory
+pub fn unpack_archive(
+    location: impl AsRef<str>,
+    format: ArchiveFormat,
+    output_path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let location = location.as_ref();
+    let output_path = output_path.as_ref();
+
+    // Remote archives are downloaded into a temporary file that lives until unpacking is done
+    let mut downloaded = None;
+    let archive_path = if is_remote(location) {
+        let temp_file = NamedTempFile::new().context("Failed to create temporary file")?;
+        download(location, temp_file.path())?;
+        downloaded.insert(temp_file).path().to_path_buf()
+    } else {
+        local_path(location)
+    };
+
+    let file = File::open(&archive_path)
+        .with_context(|| format!("Failed to open archive {}", archive_path.display()))?;
+    let reader = BufReader::new(file);
+
+    match format {
+        ArchiveFormat::TarGz => tar::Archive::new(GzDecoder::new(reader)).unpack(output_path),
+        ArchiveFormat::TarZst => zstd::Decoder::with_buffer(reader)
+            .and_then(|d| tar::Archive::new(d).unpack(output_path)),
+        ArchiveFormat::Zip => ZipArchive::new(reader)
+            .and_then(|mut archive| archive.extract(output_path))
+            .map_err(io::Error::other),
+    }
+    .with_context(|| format!("Failed to unpack archive {}", location))?;
+
+    Ok(())
+}
+
+/// Packs the content of the directory into an archive file
+pub fn pack_archive(
+    dir: impl AsRef<Path>,
+    format: ArchiveFormat,
+    output_path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let dir = dir.as_ref();
+    let output_path = output_path.as_ref();
+    let file = File::create(output_path)
+        .with_context(|| format!("Failed to create archive {}", output_path.display()))?;
+
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            builder.follow_symlinks(false);
+            builder.append_dir_all(".", dir)?;
+            builder.into_inner()?.finish()?;
+        }
+        ArchiveFormat::TarZst => {
+            let mut builder = tar::Builder::new(zstd::Encoder::new(file, 0)?);
+            builder.follow_symlinks(false);
+            builder.append_dir_all(".", dir)?;
+            builder.into_inner()?.finish()?;
+        }
+        ArchiveFormat::Zip => {
+            let mut writer = ZipWriter::new(file);
+            for entry in WalkDir::new(dir).min_depth(1).sort_by_file_name() {
+                let entry = entry?;
+                let name = entry
+                    .path()
+                    .strip_prefix(dir)?
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                if entry.file_type().is_dir() {
+                    writer.add_directory(name, SimpleFileOptions::default())?;
+                } else if entry.file_type().is_symlink() {
+                    let target = fs::read_link(entry.path())?;
+                    writer.add_symlink_from_path(name, target, SimpleFileOptions::default())?;
+                } else {
+                    writer.start_file(name, SimpleFileOptions::default())?;
+                    io::copy(&mut File::open(entry.path())?, &mut writer)?;
+                }
+            }
+            writer.finish()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn download(url: &str, output_path: &Path) -> Result<(), Error> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| anyhow!("Failed to download {}: {}", url, e))?;
+
+    let mut file = File::create(output_path)
+        .with_context(|| format!("Failed to create file {}", output_path.display()))?;
+    io::copy(&mut response.into_body().into_reader(), &mut file)
+        .with_context(|| format!("Failed to download {}", url))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const FORMATS: [(ArchiveFormat, &str); 3] = [
+        (ArchiveFormat::TarGz, "silo.tar.gz"),
+        (ArchiveFormat::TarZst, "silo.tar.zst"),
+        (ArchiveFormat::Zip, "silo.zip"),
+    ];
+
+    /// Packs a small silo into an archive of the given format
+    fn archive(format: ArchiveFormat, file_name: &str) -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let silo = dir.path().join("silo");
+        fs::create_dir_all(silo.join("node/cli")).unwrap();
+        fs::write(silo.join("node/cli/.tohumrc"), "{}").unwrap();
+
+        let archive_path = dir.path().join(file_name);
+        pack_archive(&silo, format, &archive_path).unwrap();
+
+        (dir, archive_path)
+    }
+
+    fn assert_unpacked(output: &TempDir) {
+        let content = fs::read_to_string(output.path().join("node/cli/.tohumrc")).unwrap();
+        assert_eq!(content, "{}");
+    }
+
+    /// Serves the file once over HTTP and returns its URL
+    fn serve(path: &Path) -> String {
+        let body = fs::read(path).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/{}?token=x",
+            listener.local_addr().unwrap(),
+            path.file_name().unwrap().to_string_lossy()
+        );
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // The request is small enough to be read at once
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+
+        url
+    }
+
+    #[test]
+    fn detects_format() {
+        assert_eq!(
+            ArchiveFormat::detect("silo.tar.gz"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::detect("silo.TGZ"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::detect("https://example.com/silo.tar.zst?token=x"),
+            Some(ArchiveFormat::TarZst)
+        );
+        assert_eq!(
+            ArchiveFormat::detect("https://example.com/silo.zip#main"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::detect("https://example.com/silo?f=a.zip"),
+            None
+        );
+        assert_eq!(ArchiveFormat::detect("https://github.com/x/silo.git"), None);
+    }
+
+    #[test]
+    fn unpacks_local_archive() {
+        for (format, file_name) in FORMATS {
+            let (_dir, archive_path) = archive(format, file_name);
+            let output = TempDir::new().unwrap();
+            unpack_archive(archive_path.to_string_lossy(), format, output.path()).unwrap();
+
+            assert_unpacked(&output);
+        }
+    }
+
+    #[test]
+    fn unpacks_file_url() {
+        for (format, file_name) in FORMATS {
+            let (_dir, archive_path) = archive(format, file_name);
+            let output = TempDir::new().unwrap();
+            let url = format!("file://{}", archive_path.display());
+            unpack_archive(url, format, output.path()).unwrap();
+
+            assert_unpacked(&output);
+        }
+    }
+
+    #[test]
+    fn unpacks_remote_archive() {
+        for (format, file_name) in FORMATS {
+            let (_dir, archive_path) = archive(format, file_name);
+            let output = TempDir::new().unwrap();
+            let url = serve(&archive_path);
+            assert_eq!(ArchiveFormat::detect(&url), Some(format));
+            unpack_archive(url, format, output.path()).unwrap();
+
+            assert_unpacked(&output);
+        }
+    }
+}
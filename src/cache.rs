@@ -0,0 +1,229 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::config_path,
+    constants::{CACHE_DIR_NAME, CACHE_ENTRY_FILENAME, CACHE_REPO_DIR_NAME},
+    git::GitBackendKind,
+};
+
+/// Metadata of a cached silo, stored next to its clone
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CacheEntry {
+    pub url: String,
+    /// Branch, tag or commit SHA that is cached
+    pub reference: String,
+    /// SHA of the commit that the reference was resolved to
+    pub commit: String,
+    /// Git backend that the silo is cloned with
+    pub backend: GitBackendKind,
+    /// URL that the silo was last fetched from, either the silo URL or one of its mirrors
+    #[serde(default)]
+    pub fetched_url: Option<String>,
+    /// Unix timestamp (in seconds) of the last successful update
+    pub updated_at: u64,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl CacheEntry {
+    pub fn repo_path(&self) -> PathBuf {
+        self.path.join(CACHE_REPO_DIR_NAME)
+    }
+
+    /// Updates the timestamp and writes the entry to the disk
+    pub fn touch(&mut self) -> Result<(), Error> {
+        self.updated_at = now();
+
+        let file = File::create(self.path.join(CACHE_ENTRY_FILENAME))
+            .with_context(|| format!("Failed to write cache entry {}", self.path.display()))?;
+        serde_json::to_writer_pretty(file, self)?;
+
+        Ok(())
+    }
+}
+
+pub fn cache_dir() -> Result<PathBuf, Error> {
+    let cache_dir = config_path()?.join(CACHE_DIR_NAME);
+
+    if !cache_dir.is_dir() {
+        fs::create_dir_all(&cache_dir)
+            .with_context(|| format!("Failed to create cache directory {}", cache_dir.display()))?;
+    }
+
+    Ok(cache_dir)
+}
+
+/// Returns the cache entry for the given silo. If the silo is not cached yet,
+/// the entry is not written to the disk until `CacheEntry::touch` is called.
+/// `backend` is only used if the silo is not cached yet.
+pub fn cache_entry(
+    url: impl AsRef<str>,
+    reference: impl AsRef<str>,
+    backend: GitBackendKind,
+) -> Result<CacheEntry, Error> {
+    let url = url.as_ref();
+    let reference = reference.as_ref();
+    let path = cache_dir()?.join(cache_key(url, reference));
+
+    if path.join(CACHE_ENTRY_FILENAME).is_file() {
+        let entry = read_cache_entry(path.clone())?;
+        if entry.url == url && entry.reference == reference {
+            return Ok(entry);
+        }
+
+        // The directory belongs to another silo, it is cloned again from scratch
+        remove_cache_entry(&path)?;
+    }
+
+    Ok(CacheEntry {
+        url: url.to_string(),
+        reference: reference.to_string(),
+        commit: String::new(),
+        backend,
+        fetched_url: None,
+        updated_at: 0,
+        path,
+    })
+}
+
+fn read_cache_entry(path: PathBuf) -> Result<CacheEntry, Error> {
+    let entry_path = path.join(CACHE_ENTRY_FILENAME);
+    let reader = BufReader::new(File::open(&entry_path)?);
+    let mut entry: CacheEntry = serde_json::from_reader(reader)
+        .with_context(|| format!("Invalid cache entry {}", entry_path.display()))?;
+    entry.path = path;
+
+    Ok(entry)
+}
+
+/// Lists all the silos found in the cache directory
+pub fn list_cache_entries() -> Result<Vec<CacheEntry>, Error> {
+    let mut entries = vec![];
+
+    for dir_entry in fs::read_dir(cache_dir()?)? {
+        let path = dir_entry?.path();
+
+        if !path.join(CACHE_ENTRY_FILENAME).is_file() {
+            continue;
+        }
+
+        entries.push(read_cache_entry(path)?);
+    }
+
+    entries.sort_by(|a, b| a.url.cmp(&b.url).then(a.reference.cmp(&b.reference)));
+
+    Ok(entries)
+}
+
+pub fn clear_cache() -> Result<(), Error> {
+    let cache_dir = cache_dir()?;
+
+    fs::remove_dir_all(&cache_dir)
+        .with_context(|| format!("Failed to remove cache directory {}", cache_dir.display()))?;
+
+    Ok(())
+}
+
+pub fn remove_cache_entry(path: impl AsRef<Path>) -> Result<(), Error> {
+    let path = path.as_ref();
+
+    if path.exists() {
+        fs::remove_dir_all(path)
+            .with_context(|| format!("Failed to remove cached silo {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Builds a file system friendly directory name from the silo URL and reference.
+/// The readable part is lossy, so it is followed by a hash of the exact URL and reference.
+/// e.g `https://github.com/mkaramuk/tohum.git` + `main` -> `github.com_mkaramuk_tohum.git@main-a81cda5d7799`
+fn cache_key(url: &str, reference: &str) -> String {
+    let name = url
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(url)
+        .trim_end_matches('/');
+    let name = format!("{}@{}", name, reference)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let hash = Sha256::digest(format!("{}@{}", url, reference));
+
+    format!("{}-{}", name, &hex::encode(hash)[..12])
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Formats how much time has passed since the given Unix timestamp
+pub fn format_elapsed(timestamp: u64) -> String {
+    let elapsed = now().saturating_sub(timestamp);
+
+    match elapsed {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{} minutes ago", elapsed / 60),
+        3600..86400 => format!("{} hours ago", elapsed / 3600),
+        _ => format!("{} days ago", elapsed / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_keys_are_unique() {
+        let key = cache_key("https://github.com/mkaramuk/tohum.git", "main");
+        assert!(
+            key.starts_with("github.com_mkaramuk_tohum.git@main-"),
+            "{}",
+            key
+        );
+        assert_eq!(
+            key,
+            cache_key("https://github.com/mkaramuk/tohum.git", "main")
+        );
+
+        let pairs = [
+            (
+                ("/tmp/coll/a/b/silo.git", "main"),
+                ("/tmp/coll/a_b/silo.git", "main"),
+            ),
+            (
+                ("https://host/org_x/repo", "main"),
+                ("https://host/org/x_repo", "main"),
+            ),
+            (
+                ("http://host/org/repo", "main"),
+                ("https://host/org/repo", "main"),
+            ),
+            (
+                ("https://host/org/repo", "a/b"),
+                ("https://host/org/repo", "a_b"),
+            ),
+        ];
+        for ((url_a, ref_a), (url_b, ref_b)) in pairs {
+            assert_ne!(cache_key(url_a, ref_a), cache_key(url_b, ref_b));
+        }
+    }
+}
@@ -1,19 +1,45 @@
 use clap::{Arg, ArgAction, Command};
 
-use crate::constants::{DEFAULT_SILO, DEFAULT_SILO_BRANCH};
+use crate::constants::DEFAULT_SILO_REF;
 
 pub const CMD_PLANT: &str = "plant";
 pub const CMD_SILO: &str = "silo";
 pub const CMD_SILO_LIST: &str = "list";
 pub const CMD_SILO_INSPECT: &str = "inspect";
+pub const CMD_SILO_INFO: &str = "info";
+pub const CMD_SILO_ADD: &str = "add";
+pub const CMD_SILO_REMOVE: &str = "remove";
+pub const CMD_SILO_SET_DEFAULT: &str = "set-default";
+pub const CMD_SILO_SOURCES: &str = "sources";
+pub const CMD_SILO_CACHE: &str = "cache";
+pub const CMD_SILO_CACHE_LIST: &str = "list";
+pub const CMD_SILO_CACHE_CLEAR: &str = "clear";
+pub const CMD_SILO_CACHE_REFRESH: &str = "refresh";
+pub const CMD_SILO_INDEX: &str = "index";
+pub const CMD_SILO_INDEX_BUILD: &str = "build";
+pub const CMD_SILO_MANIFEST: &str = "manifest";
+pub const CMD_SILO_MANIFEST_BUILD: &str = "build";
+pub const CMD_SILO_VENDOR: &str = "vendor";
+pub const CMD_SEED: &str = "seed";
+pub const CMD_SEED_LINT: &str = "lint";
 
 pub const ARGS_SILO_URL: &str = "silo-url";
-pub const ARGS_SILO_BRANCH: &str = "silo-branch";
+pub const ARGS_SILO_REF: &str = "silo-ref";
 pub const ARGS_SEED: &str = "seed";
 pub const ARGS_PROJECT_NAME: &str = "project-name";
 pub const ARGS_VARIABLES: &str = "var";
 pub const ARGS_PATH: &str = "path";
 pub const ARGS_FORCE: &str = "force";
+pub const ARGS_OFFLINE: &str = "offline";
+pub const ARGS_STRICT: &str = "strict";
+pub const ARGS_DENY_WARNINGS: &str = "deny-warnings";
+pub const ARGS_SILO_NAME: &str = "name";
+pub const ARGS_URL: &str = "url";
+pub const ARGS_TOKEN_ENV: &str = "token-env";
+pub const ARGS_MIRROR: &str = "mirror";
+pub const ARGS_DESTINATION: &str = "destination";
+pub const ARGS_TRUST: &str = "trust";
+pub const ARGS_NO_TRUST: &str = "no-trust";
 
 pub fn build_cmd() -> Command {
     let silo_source_args = build_args_silo_source();
@@ -25,8 +51,23 @@ pub fn build_cmd() -> Command {
         .arg_required_else_help(true)
         .arg(&silo_source_args[0])
         .arg(&silo_source_args[1])
+        .arg(
+            Arg::new(ARGS_OFFLINE)
+                .long("offline")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Never touch the network, only use the cached or local silos"),
+        )
+        .arg(
+            Arg::new(ARGS_STRICT)
+                .long(ARGS_STRICT)
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Fail if the silo has invalid seeds instead of skipping them"),
+        )
         .subcommand(build_sub_cmd_plant())
         .subcommand(build_sub_cmd_silo())
+        .subcommand(build_sub_cmd_seed())
 }
 
 pub fn build_args_silo_source() -> Vec<Arg> {
@@ -35,18 +76,19 @@ pub fn build_args_silo_source() -> Vec<Arg> {
             .short('s')
             .long("silo")
             .action(ArgAction::Set)
-            .default_value(DEFAULT_SILO)
-            // .hide_default_value(true)
             .global(true)
-            .help("Git URL of the silo"),
-        Arg::new(ARGS_SILO_BRANCH)
-            .short('b')
-            .long("branch")
+            .help("Name of a registered silo or URL/path of the silo. If not given, uses the default silo."),
+        Arg::new(ARGS_SILO_REF)
+            .short('r')
+            .long("ref")
+            .visible_alias("branch")
+            .short_alias('b')
             .action(ArgAction::Set)
-            // .hide_default_value(true)
             .global(true)
-            .default_value(DEFAULT_SILO_BRANCH)
-            .help("Git branch of the silo"),
+            .help(format!(
+                "Git branch, tag or commit SHA of the silo. If not given, uses the one registered with the silo or \"{}\".",
+                DEFAULT_SILO_REF
+            )),
     ]
 }
 
@@ -90,6 +132,19 @@ pub fn build_sub_cmd_plant() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Overwrite if the given path already exists."),
         )
+        .arg(
+            Arg::new(ARGS_TRUST)
+                .long(ARGS_TRUST)
+                .action(ArgAction::SetTrue)
+                .conflicts_with(ARGS_NO_TRUST)
+                .help("Plant from the silo even if it is not trusted, without asking."),
+        )
+        .arg(
+            Arg::new(ARGS_NO_TRUST)
+                .long(ARGS_NO_TRUST)
+                .action(ArgAction::SetTrue)
+                .help("Refuse to plant from a silo that is not trusted, without asking."),
+        )
 }
 
 pub fn build_sub_cmd_silo() -> Command {
@@ -100,6 +155,9 @@ pub fn build_sub_cmd_silo() -> Command {
                 .alias("ls")
                 .about("Lists all the available seeds from the silo."),
         )
+        .subcommand(
+            Command::new(CMD_SILO_INFO).about("Shows details of the silo itself."),
+        )
         .subcommand(
             Command::new(CMD_SILO_INSPECT)
                 .alias("details")
@@ -112,4 +170,161 @@ pub fn build_sub_cmd_silo() -> Command {
                         .help("Name of the seed in the silo"),
                 ),
         )
+        .subcommand(
+            Command::new(CMD_SILO_ADD)
+                .about("Registers a new silo with a name. Use \"--ref\" to pin it to a branch, tag or commit.")
+                .arg(
+                    Arg::new(ARGS_SILO_NAME)
+                        .num_args(1)
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .help("Name of the silo"),
+                )
+                .arg(
+                    Arg::new(ARGS_URL)
+                        .num_args(1)
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .help("URL or path of the silo"),
+                )
+                .arg(
+                    Arg::new(ARGS_TOKEN_ENV)
+                        .long(ARGS_TOKEN_ENV)
+                        .value_name("VAR")
+                        .num_args(1)
+                        .action(ArgAction::Set)
+                        .help("Environment variable that holds the access token of a private HTTPS silo"),
+                )
+                .arg(
+                    Arg::new(ARGS_MIRROR)
+                        .long(ARGS_MIRROR)
+                        .value_name("URL")
+                        .action(ArgAction::Append)
+                        .help("URL of a mirror that is tried when the silo cannot be fetched. Can be given multiple times."),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_SILO_REMOVE)
+                .alias("rm")
+                .about("Removes a registered silo.")
+                .arg(
+                    Arg::new(ARGS_SILO_NAME)
+                        .num_args(1)
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .help("Name of the silo"),
+                ),
+        )
+        .subcommand(
+            Command::new(CMD_SILO_SET_DEFAULT)
+                .about("Sets the silo that is used when no silo is given.")
+                .arg(
+                    Arg::new(ARGS_SILO_NAME)
+                        .num_args(1)
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .help("Name of the silo"),
+                ),
+        )
+        .subcommand(Command::new(CMD_SILO_SOURCES).about("Lists all the registered silos."))
+        .subcommand(build_sub_cmd_silo_cache())
+        .subcommand(build_sub_cmd_silo_index())
+        .subcommand(build_sub_cmd_silo_manifest())
+        .subcommand(
+            Command::new(CMD_SILO_VENDOR)
+                .about("Exports a snapshot of the silo into a directory or a .tar.gz, .tar.zst or .zip archive.")
+                .arg(
+                    Arg::new(ARGS_DESTINATION)
+                        .num_args(1)
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .help("Directory or archive file to export the silo into"),
+                )
+                .arg(
+                    Arg::new(ARGS_SEED)
+                        .long("seed")
+                        .action(ArgAction::Append)
+                        .help("Name of a seed to export, with an optional version requirement. Can be given multiple times. If not given, exports all the seeds."),
+                )
+                .arg(
+                    Arg::new(ARGS_FORCE)
+                        .short('f')
+                        .long("force")
+                        .action(ArgAction::SetTrue)
+                        .help("Overwrite if the destination already exists."),
+                ),
+        )
+}
+
+pub fn build_sub_cmd_silo_cache() -> Command {
+    Command::new(CMD_SILO_CACHE)
+        .about("Manages the locally cached silos.")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(CMD_SILO_CACHE_LIST)
+                .alias("ls")
+                .about("Lists all the cached silos."),
+        )
+        .subcommand(Command::new(CMD_SILO_CACHE_CLEAR).about("Removes all the cached silos."))
+        .subcommand(
+            Command::new(CMD_SILO_CACHE_REFRESH)
+                .about("Fetches the latest state of all the cached silos."),
+        )
+}
+
+pub fn build_sub_cmd_silo_index() -> Command {
+    Command::new(CMD_SILO_INDEX)
+        .about("Manages the index file of a silo.")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(CMD_SILO_INDEX_BUILD)
+                .about("Generates the index file of a local silo from its seeds.")
+                .arg(
+                    Arg::new(ARGS_PATH)
+                        .num_args(1)
+                        .default_value(".")
+                        .action(ArgAction::Set)
+                        .help("Root directory of the silo. If not given, uses current directory."),
+                ),
+        )
+}
+
+pub fn build_sub_cmd_silo_manifest() -> Command {
+    Command::new(CMD_SILO_MANIFEST)
+        .about("Manages the checksum manifest of a silo.")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(CMD_SILO_MANIFEST_BUILD)
+                .about("Generates the checksum manifest of a local silo. Sign it with minisign afterwards.")
+                .arg(
+                    Arg::new(ARGS_PATH)
+                        .num_args(1)
+                        .default_value(".")
+                        .action(ArgAction::Set)
+                        .help("Root directory of the silo. If not given, uses current directory."),
+                ),
+        )
+}
+
+pub fn build_sub_cmd_seed() -> Command {
+    Command::new(CMD_SEED)
+        .about("Seed authoring commands.")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new(CMD_SEED_LINT)
+                .about("Checks a seed directory for the common mistakes.")
+                .arg(
+                    Arg::new(ARGS_PATH)
+                        .num_args(1)
+                        .default_value(".")
+                        .action(ArgAction::Set)
+                        .help("Directory of the seed. If not given, uses current directory."),
+                )
+                .arg(
+                    Arg::new(ARGS_DENY_WARNINGS)
+                        .long(ARGS_DENY_WARNINGS)
+                        .action(ArgAction::SetTrue)
+                        .help("Fail if there are warnings, e.g unused variables"),
+                ),
+        )
 }
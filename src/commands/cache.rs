@@ -0,0 +1,101 @@
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::{
+    cache::{clear_cache, format_elapsed, list_cache_entries},
+    cmd::ARGS_OFFLINE,
+    config::Config,
+    progress::create_spinner,
+    silo::{SiloOptions, update_cached_silo},
+};
+
+pub fn cache_list() -> anyhow::Result<()> {
+    let entries = list_cache_entries()?;
+
+    if entries.is_empty() {
+        println!("No cached silos.");
+        return Ok(());
+    }
+
+    println!(
+        "📦 {} {} {}",
+        "Found".white(),
+        entries.len().to_string().green().bold(),
+        "cached silos:".white()
+    );
+
+    println!("{}", "─".repeat(40).bright_black());
+    for entry in &entries {
+        println!(
+            "  {} {} {}",
+            "•".cyan().bold(),
+            entry.url.cyan().bold(),
+            format!("({})", entry.reference).bright_black()
+        );
+        println!("    {} {}", "commit".bright_black(), entry.commit.yellow());
+        println!(
+            "    {} {}",
+            "updated".bright_black(),
+            format_elapsed(entry.updated_at)
+        );
+        println!(
+            "    {}",
+            entry.path.to_string_lossy().bright_black().italic()
+        );
+        println!();
+    }
+
+    Ok(())
+}
+
+pub fn cache_clear() -> anyhow::Result<()> {
+    clear_cache()?;
+    println!("Silo cache cleared!");
+
+    Ok(())
+}
+
+pub fn cache_refresh(cmd_matches: &ArgMatches, config: &Config) -> anyhow::Result<()> {
+    if config.offline || cmd_matches.get_flag(ARGS_OFFLINE) {
+        return Err(anyhow::anyhow!(
+            "Cached silos cannot be refreshed in offline mode"
+        ));
+    }
+
+    let options = SiloOptions::new(config, false, false);
+    let entries = list_cache_entries()?;
+    let mut failed = 0;
+
+    for mut entry in entries {
+        let spinner = create_spinner(&format!("Refreshing {}...", entry.url));
+        let silo = config.silo_for_url(&entry.url, Some(&entry.reference));
+        let urls = options.fetch_urls(&silo);
+        let result = update_cached_silo(&mut entry, &urls, &silo);
+        spinner.finish_and_clear();
+
+        match result {
+            Ok(_) => println!(
+                "  {} {} {}",
+                "✓".green().bold(),
+                entry.url.cyan(),
+                format!("({})", entry.reference).bright_black()
+            ),
+            Err(e) => {
+                failed += 1;
+                eprintln!(
+                    "{}: Could not refresh {} ({}): {}",
+                    "Error".red().bold(),
+                    entry.url.cyan(),
+                    entry.reference,
+                    e.to_string().trim()
+                );
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} silo(s) could not be refreshed", failed));
+    }
+
+    Ok(())
+}
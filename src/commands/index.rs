@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::{
+    cmd::{ARGS_PATH, ARGS_STRICT},
+    index::SiloIndex,
+    silo::{format_diagnostics, walk_silo},
+};
+
+pub fn index_build(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
+    let silo_path = PathBuf::from(cmd_matches.get_one::<String>(ARGS_PATH).unwrap());
+
+    if !silo_path.is_dir() {
+        return Err(anyhow!(
+            "Silo directory {} doesn't exist",
+            silo_path.display()
+        ));
+    }
+
+    let (seeds, diagnostics) = walk_silo(&silo_path)?;
+    if !diagnostics.is_empty() {
+        if cmd_matches.get_flag(ARGS_STRICT) {
+            return Err(anyhow!(
+                "Silo has {} invalid seed(s):\n{}",
+                diagnostics.len(),
+                format_diagnostics(&diagnostics)
+            ));
+        }
+
+        eprintln!(
+            "{}: {} invalid seed(s) are not indexed:\n{}",
+            "Warning".yellow(),
+            diagnostics.len(),
+            format_diagnostics(&diagnostics)
+        );
+    }
+
+    let index = SiloIndex::new(seeds);
+    let index_path = index.write(&silo_path)?;
+
+    println!(
+        "Indexed {} seed(s) into {}",
+        index.seeds.len().to_string().cyan(),
+        index_path.display().to_string().cyan()
+    );
+    println!(
+        "{}",
+        "Rebuild the index whenever a seed is added, removed or changed.".bright_black()
+    );
+
+    Ok(())
+}
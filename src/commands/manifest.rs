@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::{cmd::ARGS_PATH, manifest::SiloManifest};
+
+pub fn manifest_build(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
+    let silo_path = PathBuf::from(cmd_matches.get_one::<String>(ARGS_PATH).unwrap());
+
+    if !silo_path.is_dir() {
+        return Err(anyhow!(
+            "Silo directory {} doesn't exist",
+            silo_path.display()
+        ));
+    }
+
+    let manifest = SiloManifest::build(&silo_path)?;
+    let manifest_path = manifest.write(&silo_path)?;
+
+    println!(
+        "Listed {} file(s) in {}",
+        manifest.digests.len().to_string().cyan(),
+        manifest_path.display().to_string().cyan()
+    );
+    println!(
+        "{}",
+        format!(
+            "Sign it with \"minisign -Sm {}\" and rebuild it whenever a file is changed.",
+            manifest_path.display()
+        )
+        .bright_black()
+    );
+
+    Ok(())
+}
@@ -1,2 +1,8 @@
+pub mod cache;
+pub mod index;
+pub mod manifest;
 pub mod plant;
+pub mod seed;
 pub mod silo;
+pub mod sources;
+pub mod vendor;
@@ -1,114 +1,121 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
+};
 
 use anyhow::{Context, anyhow};
 use clap::ArgMatches;
 use colored::Colorize;
-use tempfile::TempDir;
-use tera::Tera;
+use inquire::{Confirm, MultiSelect, Select, Text, validator::Validation};
+use serde_json::Value;
 use walkdir::WalkDir;
 
 use crate::{
     cmd::{
-        ARGS_FORCE, ARGS_PATH, ARGS_PROJECT_NAME, ARGS_SEED, ARGS_SILO_BRANCH, ARGS_SILO_URL,
-        ARGS_VARIABLES,
+        ARGS_FORCE, ARGS_NO_TRUST, ARGS_OFFLINE, ARGS_PATH, ARGS_PROJECT_NAME, ARGS_SEED,
+        ARGS_SILO_REF, ARGS_SILO_URL, ARGS_STRICT, ARGS_TRUST, ARGS_VARIABLES,
     },
-    constants::TOHUMRC_FILENAME,
-    git::git_sparse_clone,
+    config::Config,
+    constants::{BUILTIN_VARIABLES, TOHUMRC_FILENAME},
     io::{copy_dir_recursive, is_binary},
     log_err_recursive,
     progress::create_spinner,
-    silo::{self, read_silo},
+    seed::{Seed, SeedTemplateVariable, VariableType, display_value, parse_seed_spec},
+    silo::{SiloHandle, SiloOptions, find_seed, seed_versions},
+    template::{dependency_order, new_template},
 };
 
-pub fn plant_seed(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
+pub fn plant_seed(cmd_matches: &ArgMatches, config: &mut Config) -> anyhow::Result<()> {
     let project_name = cmd_matches.get_one::<String>(ARGS_PROJECT_NAME).unwrap();
-    let silo_branch = cmd_matches.get_one::<String>(ARGS_SILO_BRANCH).unwrap();
-    let silo_url = cmd_matches.get_one::<String>(ARGS_SILO_URL).unwrap();
-    let seed_name = cmd_matches.get_one::<String>(ARGS_SEED).unwrap();
+    let (silos, seed_name) = config.resolve_seed_silos(
+        cmd_matches.get_one::<String>(ARGS_SEED).unwrap(),
+        cmd_matches.get_one::<String>(ARGS_SILO_URL),
+        cmd_matches.get_one::<String>(ARGS_SILO_REF),
+    )?;
     let path = cmd_matches.get_one::<String>(ARGS_PATH).unwrap();
     let force = cmd_matches.get_one::<bool>(ARGS_FORCE).unwrap();
+    let options = SiloOptions::new(
+        config,
+        cmd_matches.get_flag(ARGS_OFFLINE),
+        cmd_matches.get_flag(ARGS_STRICT),
+    );
     let spinner = create_spinner("Fetching silo...");
 
     let mut project_path = PathBuf::from(path);
     project_path.push(project_name);
 
-    if project_path.exists() {
-        if !force {
-            return Err(anyhow!(
-                "Target dir {} already exists. Either use another path, project name or \"--force\" flag to overwrite",
-                project_path.to_string_lossy().cyan()
-            ));
-        }
-
-        if project_path.is_file() {
-            fs::remove_file(&project_path)?;
-        } else if project_path.is_dir() {
-            fs::remove_dir_all(&project_path)?;
-        }
+    if project_path.exists() && !force {
+        return Err(anyhow!(
+            "Target dir {} already exists. Either use another path, project name or \"--force\" flag to overwrite",
+            project_path.to_string_lossy().cyan()
+        ));
     }
 
-    let silo_path = PathBuf::from(silo_url);
-    let seeds = if silo_path.exists() && silo_path.is_dir() {
-        read_silo(silo_path)?
-    } else {
-        silo::fetch_silo_from_git(silo_url, silo_branch)?
-    };
-    let seed = seeds
-        .iter()
-        .find(|s| s.name == *seed_name)
-        .with_context(|| format!("Seed {} is not found in the silo", seed_name))?;
-
-    let temp_dir = TempDir::new().context("Failed to create temporary directory")?;
-    let temp_path = temp_dir.path();
+    let (seed_name, version_req) = parse_seed_spec(seed_name)?;
+    let (silo, seed) = find_seed(&silos, seed_name, version_req.as_ref(), &options)?;
 
-    let glob_pattern = PathBuf::from(&seed.repo_path).join("*");
-    let mut seed_repo_path = temp_path.to_path_buf();
-    seed_repo_path.push(&seed.repo_path);
-
-    git_sparse_clone(silo_url, silo_branch, &glob_pattern, temp_path)?;
-
-    let mut project_dir = PathBuf::from(&path);
-    project_dir.push(project_name);
+    if let Some(metadata) = silo.read()?.metadata
+        && !metadata.is_compatible()
+    {
+        return Err(anyhow!(
+            "Silo {} requires tohum {} but this is tohum {}. Upgrade tohum with \"cargo install tohum\"",
+            silo.display_name().cyan(),
+            metadata.tohum.as_ref().unwrap().to_string().cyan(),
+            env!("CARGO_PKG_VERSION")
+        ));
+    }
 
-    spinner.set_message("Setting up the project...");
-    copy_dir_recursive(seed_repo_path, &project_dir)?;
+    if !seed.is_compatible() {
+        let compatible_version = seed_versions(silo.seeds()?, seed_name)
+            .into_iter()
+            .find(|s| {
+                s.is_compatible()
+                    && version_req
+                        .as_ref()
+                        .is_none_or(|req| req.matches(&s.version))
+            });
+        let hint = match compatible_version {
+            Some(s) => format!(
+                " or plant an older version of the seed with \"{}@={}\"",
+                seed_name, s.version
+            ),
+            None => String::new(),
+        };
 
-    let mut variables = tera::Context::new();
-    if let Some(arg_var) = cmd_matches.get_many::<String>(ARGS_VARIABLES) {
-        for arg in arg_var {
-            let (key, val) = arg
-                .split_once('=')
-                .with_context(|| format!("Invalid variable format: {}. Use 'key=value'", arg))?;
-            variables.insert(key, val);
-        }
+        return Err(anyhow!(
+            "Seed {} v{} requires tohum {} but this is tohum {}. Upgrade tohum with \"cargo install tohum\"{}",
+            seed_name.cyan(),
+            seed.version,
+            seed.tohum.as_ref().unwrap().to_string().cyan(),
+            env!("CARGO_PKG_VERSION"),
+            hint
+        ));
     }
+    // The variables are not asked for a silo that is refused anyway
+    spinner.suspend(|| confirm_silo_trust(&silo, cmd_matches, config))?;
 
-    // Validate the variables
-    if let Some(seed_variables) = &seed.variables {
-        for (name, info) in seed_variables {
-            if !variables.contains_key(name) {
-                if info.required && info.default.is_none() {
-                    return Err(anyhow!(format!(
-                        "Template variable {} is required but not a value given",
-                        name.blue()
-                    )));
-                }
+    // Nothing is written before the variables are known to be valid
+    let variables =
+        spinner.suspend(|| template_variables(cmd_matches, &seed, project_name, &silo))?;
+    let seed_repo_path = silo.checkout_seed(&seed)?;
 
-                // If a default value is defined then use it
-                if let Some(default_value) = &info.default {
-                    variables.insert(name, default_value);
-                }
-            }
-        }
+    let project_dir = project_path.as_path();
+
+    // The existing project is overwritten only once the seed is ready to be planted
+    if project_dir.is_file() {
+        fs::remove_file(project_dir)?;
+    } else if project_dir.is_dir() {
+        fs::remove_dir_all(project_dir)?;
     }
 
-    // Default variables
-    variables.insert("project_name", project_name);
-    variables.insert("authors", &seed.authors);
+    spinner.set_message("Setting up the project...");
+    copy_dir_recursive(seed_repo_path, project_dir)?;
 
     spinner.set_message("Planting the seed...");
 
-    for entry_result in WalkDir::new(&project_dir) {
+    for entry_result in WalkDir::new(project_dir) {
         let entry = match entry_result {
             Ok(e) => e,
             Err(err) => {
@@ -121,8 +128,9 @@ pub fn plant_seed(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
             }
         };
 
+        // Symlinks are not followed, they may point outside of the project
         let path = entry.path();
-        if !path.is_file() || is_binary(path) {
+        if !entry.file_type().is_file() || is_binary(path) {
             continue;
         }
 
@@ -131,8 +139,7 @@ pub fn plant_seed(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
         // Render the template
         match fs::read_to_string(path) {
             Ok(content) => {
-                let mut template = Tera::default();
-                template.add_raw_template("template", &content)?;
+                let template = new_template("template", &content)?;
 
                 match template.render("template", &variables) {
                     Ok(rendered) => {
@@ -170,5 +177,267 @@ pub fn plant_seed(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
         seed_name.cyan()
     );
 
+    if let Some(vendor) = silo.vendor() {
+        println!(
+            "Silo {} is a snapshot of {} ({}) taken at commit {}.",
+            silo.url().cyan(),
+            vendor.url.cyan(),
+            vendor.reference,
+            vendor.commit.as_deref().unwrap_or("-").yellow()
+        );
+    } else if let Some(commit) = silo.commit() {
+        println!(
+            "Silo {} ({}) was resolved to commit {}. Use \"-s {} --ref {}\" to plant the same seed again.",
+            silo.url().cyan(),
+            silo.reference(),
+            commit.yellow(),
+            silo.display_name(),
+            commit
+        );
+    }
+
+    Ok(())
+}
+
+/// Builds the template context from the variables given via `--var` flag, the
+/// defaults of the seed and the variables that tohum defines for every seed
+fn template_variables(
+    cmd_matches: &ArgMatches,
+    seed: &Seed,
+    project_name: &str,
+    silo: &SiloHandle,
+) -> anyhow::Result<tera::Context> {
+    let mut variables = tera::Context::new();
+    let no_variables = HashMap::new();
+    let seed_variables = seed.variables.as_ref().unwrap_or(&no_variables);
+
+    // The declared variables are converted once their conditions are known
+    let mut given_values = HashMap::new();
+    if let Some(arg_var) = cmd_matches.get_many::<String>(ARGS_VARIABLES) {
+        for arg in arg_var {
+            let (key, val) = arg
+                .split_once('=')
+                .with_context(|| format!("Invalid variable format: {}. Use 'key=value'", arg))?;
+
+            // Undeclared variables are passed as strings
+            if seed_variables.contains_key(key) {
+                given_values.insert(key, val);
+            } else {
+                variables.insert(key, val);
+            }
+        }
+    }
+
+    // Default variables
+    variables.insert("project_name", project_name);
+    variables.insert("authors", &seed.authors);
+    variables.insert(
+        "silo",
+        &serde_json::json!({
+            "url": silo.url(),
+            "ref": silo.reference(),
+            "commit": silo.commit(),
+        }),
+    );
+
+    // Each variable is resolved after the variables that its computed default
+    // value and its condition refer to. All the invalid values are reported at once.
+    let dependencies = seed.variable_dependencies()?;
+    let mut invalid_variables = vec![];
+    let mut invalid_names = HashSet::new();
+    for name in dependency_order(&dependencies)? {
+        let info = &seed_variables[&name];
+        if BUILTIN_VARIABLES.contains(&name.as_str()) {
+            continue;
+        }
+
+        // Variables that depend on an invalid value cannot be resolved
+        if dependencies[&name]
+            .iter()
+            .any(|d| invalid_names.contains(d))
+        {
+            invalid_names.insert(name);
+            continue;
+        }
+
+        if !is_condition_met(&name, info, &variables)? {
+            if given_values.contains_key(name.as_str()) {
+                eprintln!(
+                    "{}: Variable {} is ignored since its condition \"{}\" doesn't hold",
+                    "Warning".yellow(),
+                    name.blue(),
+                    info.when.as_deref().unwrap_or_default()
+                );
+            }
+            continue;
+        }
+
+        if let Some(value) = given_values.get(name.as_str()) {
+            match info.parse_value(value) {
+                Ok(value) => variables.insert(&name, &value),
+                Err(err) => {
+                    invalid_variables.push(format!("  {}: {}", name.blue(), err));
+                    invalid_names.insert(name);
+                }
+            }
+        } else if let Some(default) = info.computed_default() {
+            let rendered = new_template(&name, default)
+                .and_then(|tera| tera.render(&name, &variables))
+                .with_context(|| {
+                    format!(
+                        "Failed to evaluate the default value of variable {}",
+                        name.blue()
+                    )
+                })?;
+            let value = info
+                .parse_value(&rendered)
+                .with_context(|| format!("Invalid default value of variable {}", name.blue()))?;
+            variables.insert(&name, &value);
+        } else if let Some(default_value) = &info.default {
+            variables.insert(&name, default_value);
+        } else if info.required {
+            // Nothing is asked if the given values are already invalid
+            check_invalid_variables(&invalid_variables)?;
+
+            // The missing values are asked in the interactive runs
+            if !io::stdin().is_terminal() {
+                return Err(anyhow!(format!(
+                    "Template variable {} is required but not a value given",
+                    name.blue()
+                )));
+            }
+
+            variables.insert(&name, &prompt_variable(&name, info)?);
+        }
+    }
+    check_invalid_variables(&invalid_variables)?;
+
+    Ok(variables)
+}
+
+fn check_invalid_variables(invalid_variables: &[String]) -> anyhow::Result<()> {
+    if !invalid_variables.is_empty() {
+        return Err(anyhow!(
+            "Invalid value(s) given for the template variables:\n{}",
+            invalid_variables.join("\n")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks whether the condition of the variable holds with the variables resolved so far
+fn is_condition_met(
+    name: &str,
+    info: &SeedTemplateVariable,
+    variables: &tera::Context,
+) -> anyhow::Result<bool> {
+    let Some(condition) = info.condition_template() else {
+        return Ok(true);
+    };
+
+    let rendered = new_template(name, &condition)
+        .and_then(|tera| tera.render(name, variables))
+        .with_context(|| {
+            format!(
+                "Failed to evaluate the condition of variable {}",
+                name.blue()
+            )
+        })?;
+
+    Ok(rendered == "true")
+}
+
+/// Asks the value of a variable. Variables with choices are asked with a selection list.
+fn prompt_variable(name: &str, info: &SeedTemplateVariable) -> anyhow::Result<Value> {
+    let message = format!("{}:", name);
+
+    let value = match (&info.choices, info.var_type) {
+        (Some(choices), VariableType::List) => {
+            let options = choices.iter().map(display_value).collect();
+            let selected = MultiSelect::new(&message, options)
+                .with_help_message(&info.description)
+                .raw_prompt()?;
+            Value::Array(
+                selected
+                    .into_iter()
+                    .map(|option| choices[option.index].clone())
+                    .collect(),
+            )
+        }
+        (Some(choices), _) => {
+            let options = choices.iter().map(display_value).collect();
+            let selected = Select::new(&message, options)
+                .with_help_message(&info.description)
+                .raw_prompt()?;
+            choices[selected.index].clone()
+        }
+        (None, VariableType::Boolean) => Value::Bool(
+            Confirm::new(&message)
+                .with_help_message(&info.description)
+                .prompt()?,
+        ),
+        (None, _) => {
+            // Validators must own what they use
+            let variable = info.clone();
+            let validator = move |input: &str| {
+                Ok(match variable.parse_value(input) {
+                    Ok(_) => Validation::Valid,
+                    Err(err) => Validation::Invalid(err.into()),
+                })
+            };
+            let input = Text::new(&message)
+                .with_help_message(&info.description)
+                .with_validator(validator)
+                .prompt()?;
+            info.parse_value(&input)?
+        }
+    };
+
+    Ok(value)
+}
+
+/// Asks the user once whether to trust a silo that is neither registered nor trusted
+/// before. `--trust` and `--no-trust` flags answer it for the non-interactive runs.
+fn confirm_silo_trust(
+    silo: &SiloHandle,
+    cmd_matches: &ArgMatches,
+    config: &mut Config,
+) -> anyhow::Result<()> {
+    if config.is_trusted_silo(silo.resolved()) || cmd_matches.get_flag(ARGS_TRUST) {
+        return Ok(());
+    }
+
+    let untrusted_error = || {
+        anyhow!(
+            "Silo {} is not trusted. Use \"--trust\" to plant from it anyway",
+            silo.url().cyan()
+        )
+    };
+    if cmd_matches.get_flag(ARGS_NO_TRUST) || !io::stdin().is_terminal() {
+        return Err(untrusted_error());
+    }
+
+    println!(
+        "{}: Silo {} is not trusted yet. Seeds can contain anything, so plant only from the silos you trust.",
+        "Warning".yellow(),
+        silo.url().cyan()
+    );
+    println!(
+        "    {} {}",
+        "commit".bright_black(),
+        silo.commit().unwrap_or("-")
+    );
+
+    let trusted = Confirm::new("Do you trust this silo?")
+        .with_default(false)
+        .prompt()?;
+    if !trusted {
+        return Err(untrusted_error());
+    }
+
+    config.trust_silo(silo.resolved());
+    config.save()?;
+
     Ok(())
 }
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::{
+    cmd::{ARGS_DENY_WARNINGS, ARGS_PATH},
+    constants::TOHUMRC_FILENAME,
+    diagnostic::Severity,
+    lint::lint_seed,
+};
+
+pub fn seed_lint(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
+    let seed_path = PathBuf::from(cmd_matches.get_one::<String>(ARGS_PATH).unwrap());
+
+    if !seed_path.join(TOHUMRC_FILENAME).is_file() {
+        return Err(anyhow!(
+            "{} is not a seed, it doesn't have a {} file",
+            seed_path.display(),
+            TOHUMRC_FILENAME
+        ));
+    }
+
+    let diagnostics = lint_seed(&seed_path)?;
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+
+    if diagnostics.is_empty() {
+        println!(
+            "{} Seed at {} has no problems",
+            "✔".green().bold(),
+            seed_path.display().to_string().cyan()
+        );
+        return Ok(());
+    }
+
+    println!("🔍 Seed at {}", seed_path.display().to_string().cyan());
+    println!("{}", "─".repeat(40).bright_black());
+    for diagnostic in &diagnostics {
+        let severity = match diagnostic.severity {
+            Severity::Error => "Error".red().bold(),
+            Severity::Warning => "Warning".yellow().bold(),
+        };
+        println!("  {} {}", severity, diagnostic.location().bright_black());
+        println!("    {}", diagnostic.message);
+    }
+    println!();
+
+    // Warnings only fail the lint if they are denied
+    if errors > 0 || cmd_matches.get_flag(ARGS_DENY_WARNINGS) {
+        return Err(anyhow!(
+            "Found {} error(s) and {} warning(s)",
+            errors,
+            warnings
+        ));
+    }
+
+    println!("Found {} warning(s)", warnings.to_string().yellow());
+
+    Ok(())
+}
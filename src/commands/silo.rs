@@ -1,38 +1,96 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
 
-use anyhow::Context;
 use clap::ArgMatches;
 use colored::Colorize;
 
 use crate::{
-    cmd::{ARGS_SEED, ARGS_SILO_BRANCH, ARGS_SILO_URL},
+    cmd::{ARGS_OFFLINE, ARGS_SEED, ARGS_SILO_REF, ARGS_SILO_URL, ARGS_STRICT},
+    config::Config,
+    constants::SILO_METADATA_FILENAME,
     progress::create_spinner,
-    silo::{self, read_silo},
+    seed::{Seed, display_value, parse_seed_spec},
+    silo::{SiloHandle, SiloMetadata, SiloOptions, find_seed, open_silos, seed_versions},
 };
 
-pub fn silo_list(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
-    let silo_branch = cmd_matches.get_one::<String>(ARGS_SILO_BRANCH).unwrap();
-    let silo_url = cmd_matches.get_one::<String>(ARGS_SILO_URL).unwrap();
+pub fn silo_list(cmd_matches: &ArgMatches, config: &Config) -> anyhow::Result<()> {
+    let silos = config.resolve_silos(
+        cmd_matches.get_one::<String>(ARGS_SILO_URL),
+        cmd_matches.get_one::<String>(ARGS_SILO_REF),
+    )?;
+    let options = SiloOptions::new(
+        config,
+        cmd_matches.get_flag(ARGS_OFFLINE),
+        cmd_matches.get_flag(ARGS_STRICT),
+    );
     let spinner = create_spinner("Fetching silo...");
 
-    let silo_path = PathBuf::from(silo_url);
-    let seeds = if silo_path.exists() && silo_path.is_dir() {
-        read_silo(silo_path)?
-    } else {
-        silo::fetch_silo_from_git(silo_url, silo_branch)?
-    };
+    let handles = open_silos(&silos, &options)?;
+    // Only the latest version of each seed is listed
+    let mut seeds = vec![];
+    let mut silo_metadata = vec![];
+    let mut diagnostics = vec![];
+    for handle in &handles {
+        let silo = handle.read()?;
+        let silo_seeds = silo.seeds;
+        diagnostics.extend(silo.diagnostics.into_iter().map(|d| (handle, d)));
+        if let Some(metadata) = silo.metadata {
+            silo_metadata.push((handle, metadata));
+        }
+
+        let mut names: Vec<&str> = vec![];
+        for seed in &silo_seeds {
+            if !names.contains(&seed.name.as_str()) {
+                names.push(&seed.name);
+            }
+        }
+
+        for name in names {
+            let versions = seed_versions(silo_seeds.clone(), name);
+            seeds.push((handle, versions[0].clone(), versions.len()));
+        }
+    }
 
     spinner.finish_and_clear();
+    for (handle, metadata) in &silo_metadata {
+        print_silo_metadata_summary(handle, metadata);
+    }
+
     println!(
         "🌱 {} {} {}",
         "Found".white(),
         seeds.len().to_string().green().bold(),
-        "seeds in the silo:".white()
+        if handles.len() > 1 {
+            format!("seeds in {} silos:", handles.len())
+        } else {
+            "seeds in the silo:".to_string()
+        }
+        .white()
     );
 
     println!("{}", "─".repeat(40).bright_black());
-    for seed in &seeds {
-        println!("  {} {}", "•".cyan().bold(), seed.name.cyan().bold());
+    for (handle, seed, version_count) in &seeds {
+        // Seeds with the same name in different silos are
+        // shown with the silo name to be able to pick one
+        let is_clashing = seeds
+            .iter()
+            .any(|(h, s, _)| s.name == seed.name && h.display_name() != handle.display_name());
+        let name = if is_clashing {
+            format!("{}:{}", handle.display_name(), seed.name)
+        } else {
+            seed.name.clone()
+        };
+        let other_versions = if *version_count > 1 {
+            format!(" ({} versions)", version_count)
+        } else {
+            String::new()
+        };
+        println!(
+            "  {} {} {}{}",
+            "•".cyan().bold(),
+            name.cyan().bold(),
+            format!("v{}", seed.version).bright_black(),
+            other_versions.bright_black()
+        );
 
         if let Some(desc) = &seed.description {
             let dots = if desc.chars().count() > 100 {
@@ -50,39 +108,236 @@ pub fn silo_list(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
         let primary_author = &seed.authors[0];
         println!("    {} {}", "by".bright_black(), primary_author.name);
 
+        if !seed.is_compatible() {
+            println!(
+                "    {} requires tohum {}",
+                "Incompatible:".red(),
+                seed.tohum.as_ref().unwrap()
+            );
+        }
+
+        if handles.len() > 1 {
+            println!("    {} {}", "from".bright_black(), handle.display_name());
+        }
+
+        println!();
+    }
+
+    if !diagnostics.is_empty() {
+        println!(
+            "⚠️  {} {} {}",
+            "Skipped".white(),
+            diagnostics.len().to_string().yellow().bold(),
+            "invalid seed(s):".white()
+        );
+        println!("{}", "─".repeat(40).bright_black());
+        for (handle, diagnostic) in &diagnostics {
+            println!(
+                "  {} {}",
+                "•".yellow().bold(),
+                diagnostic.location().yellow()
+            );
+            println!("    {}", diagnostic.message);
+            if handles.len() > 1 {
+                println!("    {} {}", "from".bright_black(), handle.display_name());
+            }
+        }
         println!();
+        println!(
+            "{}",
+            "Use \"--strict\" to fail when a silo has invalid seeds.".bright_black()
+        );
     }
 
     Ok(())
 }
 
-pub fn silo_inspect(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
-    let silo_branch = cmd_matches.get_one::<String>(ARGS_SILO_BRANCH).unwrap();
-    let silo_url = cmd_matches.get_one::<String>(ARGS_SILO_URL).unwrap();
-    let seed_name = cmd_matches.get_one::<String>(ARGS_SEED).unwrap();
+fn print_silo_metadata_summary(handle: &SiloHandle, metadata: &SiloMetadata) {
+    println!(
+        "📚 {} {}",
+        metadata.name.bold(),
+        format!("({})", handle.display_name()).bright_black()
+    );
+    if let Some(description) = &metadata.description {
+        println!("   {}", description.italic().bright_black());
+    }
+    if let Some(homepage) = &metadata.homepage {
+        println!("   {}", homepage.blue().underline());
+    }
+    if !metadata.is_compatible() {
+        println!(
+            "   {} requires tohum {}",
+            "Incompatible:".red(),
+            metadata.tohum.as_ref().unwrap()
+        );
+    }
+    println!();
+}
+
+pub fn silo_info(cmd_matches: &ArgMatches, config: &Config) -> anyhow::Result<()> {
+    let silo = config.resolve_silo(
+        cmd_matches.get_one::<String>(ARGS_SILO_URL),
+        cmd_matches.get_one::<String>(ARGS_SILO_REF),
+    )?;
+    let options = SiloOptions::new(
+        config,
+        cmd_matches.get_flag(ARGS_OFFLINE),
+        cmd_matches.get_flag(ARGS_STRICT),
+    );
     let spinner = create_spinner("Fetching silo...");
 
-    let silo_path = PathBuf::from(silo_url);
-    let seeds = if silo_path.exists() && silo_path.is_dir() {
-        read_silo(silo_path)?
-    } else {
-        silo::fetch_silo_from_git(silo_url, silo_branch)?
-    };
+    let handle = SiloHandle::open(&silo, &options)?;
+    let silo = handle.read()?;
 
     spinner.finish_and_clear();
 
-    let seed = seeds
-        .iter()
-        .find(|s| s.name == seed_name.as_str())
-        .with_context(|| format!("Seed {} is not found in the silo", seed_name))?;
+    println!("\n{}", "─".repeat(50).bright_black());
+    match &silo.metadata {
+        Some(metadata) => {
+            println!("📚 {}", metadata.name.bold().underline());
+            if let Some(description) = &metadata.description {
+                println!("   {}", description.italic().bright_black());
+            }
+        }
+        None => println!("📚 {}", handle.display_name().bold().underline()),
+    }
+    println!("{}", "─".repeat(50).bright_black());
+
+    println!("  {} {}", "URL:".bright_black(), handle.url());
+    println!("  {} {}", "Ref:".bright_black(), handle.reference());
+    if let Some(commit) = handle.commit() {
+        println!("  {} {}", "Commit:".bright_black(), commit);
+    }
+    if let Some(vendor) = handle.vendor() {
+        println!(
+            "  {} {} ({})",
+            "Snapshot of:".bright_black(),
+            vendor.url,
+            vendor.reference
+        );
+    }
+    println!("  {} {}", "Seeds:".bright_black(), silo.seeds.len());
+    if !silo.diagnostics.is_empty() {
+        println!(
+            "  {} {} {}",
+            "Invalid seeds:".bright_black(),
+            silo.diagnostics.len().to_string().yellow(),
+            "(run \"tohum silo list\" to see the details)".bright_black()
+        );
+    }
+
+    let Some(metadata) = &silo.metadata else {
+        println!(
+            "\n{}",
+            format!(
+                "The silo doesn't have a {} file to describe itself.",
+                SILO_METADATA_FILENAME
+            )
+            .bright_black()
+        );
+        return Ok(());
+    };
+
+    if let Some(homepage) = &metadata.homepage {
+        println!(
+            "  {} {}",
+            "Homepage:".bright_black(),
+            homepage.blue().underline()
+        );
+    }
+    if let Some(default_branch) = &metadata.default_branch {
+        println!("  {} {}", "Default branch:".bright_black(), default_branch);
+    }
+    if let Some(tohum) = &metadata.tohum {
+        let compatibility = if metadata.is_compatible() {
+            String::new()
+        } else {
+            format!(" (incompatible with tohum {})", env!("CARGO_PKG_VERSION"))
+        };
+        println!(
+            "  {} {}{}",
+            "Requires tohum:".bright_black(),
+            tohum,
+            compatibility.red()
+        );
+    }
+
+    if !metadata.maintainers.is_empty() {
+        println!("\n👥 Maintainers");
+        for maintainer in &metadata.maintainers {
+            let email = if let Some(e) = &maintainer.email {
+                format!(" <{}>", e)
+            } else {
+                String::new()
+            };
+            let website = if let Some(w) = &maintainer.website {
+                format!(" - {}", w.blue().underline())
+            } else {
+                String::new()
+            };
+
+            println!(
+                "  • {} {} {}",
+                maintainer.name.white(),
+                email.dimmed(),
+                website
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn silo_inspect(cmd_matches: &ArgMatches, config: &Config) -> anyhow::Result<()> {
+    let (silos, seed_name) = config.resolve_seed_silos(
+        cmd_matches.get_one::<String>(ARGS_SEED).unwrap(),
+        cmd_matches.get_one::<String>(ARGS_SILO_URL),
+        cmd_matches.get_one::<String>(ARGS_SILO_REF),
+    )?;
+    let options = SiloOptions::new(
+        config,
+        cmd_matches.get_flag(ARGS_OFFLINE),
+        cmd_matches.get_flag(ARGS_STRICT),
+    );
+    let spinner = create_spinner("Fetching silo...");
+
+    let (seed_name, version_req) = parse_seed_spec(seed_name)?;
+    let (silo, seed) = find_seed(&silos, seed_name, version_req.as_ref(), &options)?;
+    let versions = seed_versions(silo.seeds()?, &seed.name);
+
+    spinner.finish_and_clear();
 
     println!("\n{}", "─".repeat(50).bright_black());
-    println!("🌱 {}", seed.name.bold().underline());
+    println!(
+        "🌱 {} {}",
+        seed.name.bold().underline(),
+        format!("v{}", seed.version).bright_black()
+    );
 
     if let Some(description) = &seed.description {
         println!("   {}", description.italic().bright_black());
     }
+    println!(
+        "   {} {} {}",
+        "from".bright_black(),
+        silo.display_name(),
+        format!("({})", silo.reference()).bright_black()
+    );
+    if let Some(tohum) = &seed.tohum {
+        let compatibility = if seed.is_compatible() {
+            String::new()
+        } else {
+            format!(" (incompatible with tohum {})", env!("CARGO_PKG_VERSION"))
+        };
+        println!(
+            "   {} {}{}",
+            "requires tohum".bright_black(),
+            tohum,
+            compatibility.red()
+        );
+    }
     println!("{}", "─".repeat(50).bright_black());
+
     println!("👥 Authors");
     for author in &seed.authors {
         let email = if let Some(e) = &author.email {
@@ -108,26 +363,98 @@ pub fn silo_inspect(cmd_matches: &ArgMatches) -> anyhow::Result<()> {
         println!("\n{} {}", "🏷️ Tags:".bold(), formatted_tags);
     }
 
+    if versions.len() > 1 {
+        println!("\n{}", "📦 Versions".white().bold());
+        for version in &versions {
+            let selected = if version.version == seed.version {
+                " (selected)".green().to_string()
+            } else {
+                String::new()
+            };
+            println!("  • {}{}", version.version, selected);
+        }
+    }
+
     if let Some(variables) = &seed.variables {
         println!("\n{}", "⚙️ Variables".white().bold());
         println!("  {}", "─".repeat(30).bright_black());
 
-        for (name, info) in variables {
-            let default_val = info
-                .default
-                .as_ref()
-                .map(|v| format!(" = {}", v).bright_black())
-                .unwrap_or_default();
+        let mut names = variables.keys().collect::<Vec<_>>();
+        names.sort();
 
-            println!(
-                "  {} {} {} {}",
-                name.yellow().bold(),
-                "→".bright_black(),
-                info.var_type.cyan().italic(),
-                default_val
-            );
+        // Conditional variables are shown under the variable of their condition.
+        // Variables whose conditions depend on each other are shown at the top level.
+        let mut printed = HashSet::new();
+        for name in names
+            .iter()
+            .filter(|name| seed.parent_variable(name).is_none())
+        {
+            print_variable(&seed, name, 0, &mut printed);
+        }
+        for name in &names {
+            print_variable(&seed, name, 0, &mut printed);
         }
     }
 
     Ok(())
 }
+
+/// Prints the variable of the seed with the conditional variables under it
+fn print_variable<'a>(seed: &'a Seed, name: &'a str, depth: usize, printed: &mut HashSet<&'a str>) {
+    let Some(info) = seed
+        .variables
+        .as_ref()
+        .and_then(|variables| variables.get(name))
+    else {
+        return;
+    };
+    if !printed.insert(name) {
+        return;
+    }
+
+    let indent = "    ".repeat(depth);
+    let default_val = info
+        .default
+        .as_ref()
+        .map(|v| format!(" = {}", v).bright_black())
+        .unwrap_or_default();
+    let condition = info
+        .when
+        .as_ref()
+        .map(|when| format!(" when {}", when).bright_black())
+        .unwrap_or_default();
+
+    println!(
+        "  {}{} {} {} {}{}",
+        indent,
+        name.yellow().bold(),
+        "→".bright_black(),
+        info.var_type.to_string().cyan().italic(),
+        default_val,
+        condition
+    );
+    if let Some(choices) = &info.choices {
+        println!(
+            "  {}  {} {}",
+            indent,
+            "one of".bright_black(),
+            choices
+                .iter()
+                .map(display_value)
+                .collect::<Vec<_>>()
+                .join(" | ")
+        );
+    }
+
+    let mut children = seed
+        .variables
+        .iter()
+        .flatten()
+        .map(|(child, _)| child.as_str())
+        .filter(|child| seed.parent_variable(child) == Some(name))
+        .collect::<Vec<_>>();
+    children.sort();
+    for child in children {
+        print_variable(seed, child, depth + 1, printed);
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, anyhow};
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::{
+    cmd::{ARGS_MIRROR, ARGS_SILO_NAME, ARGS_SILO_REF, ARGS_TOKEN_ENV, ARGS_URL},
+    config::{Config, SiloConfig},
+    constants::DEFAULT_SILO_REF,
+};
+
+pub fn silo_add(cmd_matches: &ArgMatches, config: &mut Config) -> anyhow::Result<()> {
+    let name = cmd_matches.get_one::<String>(ARGS_SILO_NAME).unwrap();
+    let url = cmd_matches.get_one::<String>(ARGS_URL).unwrap();
+    let reference = cmd_matches.get_one::<String>(ARGS_SILO_REF);
+    let token_env = cmd_matches.get_one::<String>(ARGS_TOKEN_ENV);
+
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(anyhow!(
+            "Invalid silo name {}. Only letters, numbers, \"-\", \"_\" and \".\" are allowed",
+            name.cyan()
+        ));
+    }
+
+    if config.find_silo(name).is_some() {
+        return Err(anyhow!(
+            "Silo {} already exists. Remove it first with \"tohum silo remove {}\"",
+            name.cyan(),
+            name
+        ));
+    }
+
+    config.silos.push(SiloConfig {
+        name: name.clone(),
+        url: url.clone(),
+        reference: reference.cloned(),
+        token_env: token_env.cloned(),
+        token: None,
+        mirrors: cmd_matches
+            .get_many::<String>(ARGS_MIRROR)
+            .map(|mirrors| mirrors.cloned().collect())
+            .unwrap_or_default(),
+    });
+    config.save()?;
+
+    println!("Silo {} added!", name.cyan());
+
+    Ok(())
+}
+
+pub fn silo_remove(cmd_matches: &ArgMatches, config: &mut Config) -> anyhow::Result<()> {
+    let name = cmd_matches.get_one::<String>(ARGS_SILO_NAME).unwrap();
+
+    let index = config
+        .silos
+        .iter()
+        .position(|s| s.name == *name)
+        .with_context(|| format!("Silo {} is not registered", name.cyan()))?;
+    config.silos.remove(index);
+
+    if config.default_silo.as_ref() == Some(name) {
+        config.default_silo = None;
+    }
+    config.save()?;
+
+    println!("Silo {} removed!", name.cyan());
+
+    Ok(())
+}
+
+pub fn silo_set_default(cmd_matches: &ArgMatches, config: &mut Config) -> anyhow::Result<()> {
+    let name = cmd_matches.get_one::<String>(ARGS_SILO_NAME).unwrap();
+
+    if config.find_silo(name).is_none() {
+        return Err(anyhow!("Silo {} is not registered", name.cyan()));
+    }
+
+    config.default_silo = Some(name.clone());
+    config.save()?;
+
+    println!("Silo {} is set as default!", name.cyan());
+
+    Ok(())
+}
+
+pub fn silo_sources(config: &Config) -> anyhow::Result<()> {
+    if config.silos.is_empty() {
+        println!("No registered silos. Add one with \"tohum silo add <name> <url>\"");
+        return Ok(());
+    }
+
+    let default_silo = config.default_silo().map(|s| s.name.as_str());
+
+    println!("📚 {}", "Registered silos:".white());
+    println!("{}", "─".repeat(40).bright_black());
+    for silo in &config.silos {
+        let default_mark = if Some(silo.name.as_str()) == default_silo {
+            " (default)".green().to_string()
+        } else {
+            String::new()
+        };
+
+        println!(
+            "  {} {}{}",
+            "•".cyan().bold(),
+            silo.name.cyan().bold(),
+            default_mark
+        );
+        println!(
+            "    {} {}",
+            silo.url,
+            format!(
+                "({})",
+                silo.reference.as_deref().unwrap_or(DEFAULT_SILO_REF)
+            )
+            .bright_black()
+        );
+        for mirror in &silo.mirrors {
+            println!("    {} {}", "mirror".bright_black(), mirror);
+        }
+        if let Some(token_env) = &silo.token_env {
+            println!(
+                "    {}",
+                format!("token from ${}", token_env).bright_black()
+            );
+        } else if silo.token.is_some() {
+            println!("    {}", "token from config".bright_black());
+        }
+        println!();
+    }
+
+    Ok(())
+}
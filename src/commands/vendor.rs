@@ -0,0 +1,332 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, anyhow};
+use clap::ArgMatches;
+use colored::Colorize;
+
+use crate::{
+    archive::{ArchiveFormat, pack_archive},
+    cache::now,
+    cmd::{
+        ARGS_DESTINATION, ARGS_FORCE, ARGS_OFFLINE, ARGS_SEED, ARGS_SILO_REF, ARGS_SILO_URL,
+        ARGS_STRICT,
+    },
+    config::Config,
+    constants::SILO_METADATA_FILENAME,
+    io::copy_dir_recursive,
+    progress::create_spinner,
+    seed::{Seed, parse_seed_spec},
+    silo::{SiloHandle, SiloOptions, seed_versions},
+    vendor::{VendorManifest, VendoredSeed},
+};
+
+pub fn silo_vendor(cmd_matches: &ArgMatches, config: &Config) -> anyhow::Result<()> {
+    let destination = PathBuf::from(cmd_matches.get_one::<String>(ARGS_DESTINATION).unwrap());
+    let archive_format = ArchiveFormat::detect(destination.to_string_lossy());
+    let silo = config.resolve_silo(
+        cmd_matches.get_one::<String>(ARGS_SILO_URL),
+        cmd_matches.get_one::<String>(ARGS_SILO_REF),
+    )?;
+    let options = SiloOptions::new(
+        config,
+        cmd_matches.get_flag(ARGS_OFFLINE),
+        cmd_matches.get_flag(ARGS_STRICT),
+    );
+
+    if destination.exists() && !cmd_matches.get_flag(ARGS_FORCE) {
+        return Err(anyhow!(
+            "Destination {} already exists. Either use another destination or \"--force\" flag to overwrite",
+            destination.display().to_string().cyan()
+        ));
+    }
+
+    let spinner = create_spinner("Fetching silo...");
+    let handle = SiloHandle::open(&silo, &options)?;
+    let seeds = select_seeds(handle.seeds()?, cmd_matches.get_many::<String>(ARGS_SEED))?;
+
+    replace_on_success(&destination, |temp_dir| {
+        let output_dir = temp_dir.join("snapshot");
+        fs::create_dir(&output_dir)?;
+
+        spinner.set_message("Exporting the seeds...");
+        for seed in &seeds {
+            let seed_path = handle.checkout_seed(seed)?;
+            copy_dir_recursive(seed_path, output_dir.join(&seed.repo_path))?;
+        }
+
+        // The silo metadata carries the tohum requirement of the silo. A symlink is
+        // not followed, it may point to any file of the machine.
+        let metadata_path = handle.path().join(SILO_METADATA_FILENAME);
+        if fs::symlink_metadata(&metadata_path).is_ok_and(|m| m.is_file()) {
+            fs::copy(&metadata_path, output_dir.join(SILO_METADATA_FILENAME))
+                .context("Failed to copy the silo metadata")?;
+        }
+
+        VendorManifest {
+            url: handle.url().to_string(),
+            reference: handle.reference().to_string(),
+            commit: handle.commit().map(str::to_string),
+            vendored_at: now(),
+            seeds: seeds
+                .iter()
+                .map(|seed| VendoredSeed {
+                    name: seed.name.clone(),
+                    version: seed.version.clone(),
+                    path: seed.repo_path.clone(),
+                })
+                .collect(),
+        }
+        .write(&output_dir)?;
+
+        match archive_format {
+            Some(format) => {
+                let archive_path = temp_dir.join("snapshot.archive");
+                pack_archive(&output_dir, format, &archive_path)?;
+                Ok(archive_path)
+            }
+            None => Ok(output_dir),
+        }
+    })?;
+    spinner.finish_and_clear();
+
+    println!(
+        "Vendored {} seed(s) of silo {} into {}",
+        seeds.len().to_string().cyan(),
+        handle.display_name().cyan(),
+        destination.display().to_string().cyan()
+    );
+    if let Some(commit) = handle.commit() {
+        println!("Snapshot is taken at commit {}.", commit.yellow());
+    }
+    println!(
+        "Use \"-s {}\" to plant from the snapshot.",
+        destination.display()
+    );
+
+    Ok(())
+}
+
+/// Exports the snapshot into a temporary directory next to the destination, so the
+/// old destination is only replaced once the export succeeds. `export` returns the
+/// path of the snapshot inside the temporary directory.
+fn replace_on_success(
+    destination: &Path,
+    export: impl FnOnce(&Path) -> anyhow::Result<PathBuf>,
+) -> anyhow::Result<()> {
+    let parent = destination
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+    let temp_dir = tempfile::Builder::new()
+        .prefix(".tohum-vendor-")
+        .tempdir_in(parent)
+        .context("Failed to create temporary directory")?;
+
+    let snapshot = export(temp_dir.path())?;
+
+    if destination.is_dir() {
+        fs::remove_dir_all(destination)?;
+    } else if destination.exists() {
+        fs::remove_file(destination)?;
+    }
+    fs::rename(&snapshot, destination)
+        .with_context(|| format!("Failed to move the snapshot into {}", destination.display()))
+}
+
+/// Picks the seeds with the given names. A name with a version requirement picks the
+/// latest matching version, otherwise all the versions are picked. If no names are
+/// given, all the seeds are picked.
+fn select_seeds<'a>(
+    seeds: Vec<Seed>,
+    seed_specs: Option<impl Iterator<Item = &'a String>>,
+) -> anyhow::Result<Vec<Seed>> {
+    let Some(seed_specs) = seed_specs else {
+        return Ok(seeds);
+    };
+
+    let mut selected: Vec<Seed> = vec![];
+    for seed_spec in seed_specs {
+        let (seed_name, version_req) = parse_seed_spec(seed_spec)?;
+        let mut versions = seed_versions(seeds.clone(), seed_name);
+        if let Some(version_req) = &version_req {
+            versions.retain(|s| version_req.matches(&s.version));
+            versions.truncate(1);
+        }
+
+        if versions.is_empty() {
+            return Err(anyhow!(
+                "Seed {} is not found in the silo",
+                seed_spec.cyan()
+            ));
+        }
+
+        for seed in versions {
+            if !selected.iter().any(|s| s.repo_path == seed.repo_path) {
+                selected.push(seed);
+            }
+        }
+    }
+
+    Ok(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        cmd::{CMD_SILO, CMD_SILO_VENDOR, build_cmd},
+        constants::{TOHUMRC_FILENAME, VENDOR_MANIFEST_FILENAME},
+    };
+
+    fn seed(name: &str, version: &str, path: &str) -> Seed {
+        let mut seed: Seed = serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": version,
+            "authors": [{ "name": "A" }],
+        }))
+        .unwrap();
+        seed.repo_path = path.to_string();
+
+        seed
+    }
+
+    fn select(specs: &[&str]) -> anyhow::Result<Vec<String>> {
+        let seeds = vec![
+            seed("@x/cli", "1.0.0", "cli-v1"),
+            seed("@x/cli", "2.0.0", "cli-v2"),
+            seed("@x/api", "1.0.0", "api"),
+        ];
+        let specs = specs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let seed_specs = (!specs.is_empty()).then(|| specs.iter());
+
+        Ok(select_seeds(seeds, seed_specs)?
+            .into_iter()
+            .map(|s| s.repo_path)
+            .collect())
+    }
+
+    #[test]
+    fn selects_seeds_by_name_and_version() {
+        assert_eq!(select(&[]).unwrap(), ["cli-v1", "cli-v2", "api"]);
+        assert_eq!(select(&["@x/cli"]).unwrap(), ["cli-v2", "cli-v1"]);
+        assert_eq!(select(&["@x/cli@^1"]).unwrap(), ["cli-v1"]);
+        assert_eq!(select(&["@x/cli@>=1"]).unwrap(), ["cli-v2"]);
+        // A seed that is picked twice is exported once
+        assert_eq!(
+            select(&["@x/api", "@x/cli@^2", "@x/cli"]).unwrap(),
+            ["api", "cli-v2", "cli-v1"]
+        );
+
+        for spec in ["@x/web", "@x/cli@^3"] {
+            let err = select(&[spec]).unwrap_err();
+            assert!(
+                err.to_string().contains("is not found"),
+                "{}: {}",
+                spec,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn replaces_destination_only_on_success() {
+        let dir = TempDir::new().unwrap();
+        let destination = dir.path().join("vendor");
+        fs::create_dir(&destination).unwrap();
+        fs::write(destination.join("old"), "old").unwrap();
+
+        let err = replace_on_success(&destination, |temp_dir| {
+            fs::write(temp_dir.join("partial"), "partial")?;
+            Err(anyhow!("export failed"))
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "export failed");
+        assert!(destination.join("old").is_file());
+
+        replace_on_success(&destination, |temp_dir| {
+            let snapshot = temp_dir.join("snapshot");
+            fs::create_dir(&snapshot)?;
+            fs::write(snapshot.join("new"), "new")?;
+            Ok(snapshot)
+        })
+        .unwrap();
+        assert!(!destination.join("old").exists());
+        assert!(destination.join("new").is_file());
+
+        // A directory can be replaced with an archive as well
+        replace_on_success(&destination, |temp_dir| {
+            let archive = temp_dir.join("snapshot.archive");
+            fs::write(&archive, "archive")?;
+            Ok(archive)
+        })
+        .unwrap();
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "archive");
+
+        // Nothing is left behind next to the destination
+        let entries = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn vendors_seeds_with_silo_metadata() {
+        let dir = TempDir::new().unwrap();
+        let silo = dir.path().join("silo");
+        for (name, path) in [("@x/cli", "node/cli"), ("@x/api", "go/api")] {
+            fs::create_dir_all(silo.join(path)).unwrap();
+            fs::write(
+                silo.join(path).join(TOHUMRC_FILENAME),
+                serde_json::json!({
+                    "name": name,
+                    "version": "1.0.0",
+                    "authors": [{ "name": "A" }],
+                })
+                .to_string(),
+            )
+            .unwrap();
+        }
+        fs::write(silo.join("node/cli/main.ts"), "main").unwrap();
+        let metadata = r#"{"name": "Silo", "tohum": ">=0.1"}"#;
+        fs::write(silo.join(SILO_METADATA_FILENAME), metadata).unwrap();
+
+        let destination = dir.path().join("vendor");
+        let matches = build_cmd()
+            .try_get_matches_from([
+                "tohum",
+                "-s",
+                silo.to_str().unwrap(),
+                CMD_SILO,
+                CMD_SILO_VENDOR,
+                destination.to_str().unwrap(),
+                "--seed",
+                "@x/cli",
+            ])
+            .unwrap();
+        let vendor_matches = matches
+            .subcommand_matches(CMD_SILO)
+            .and_then(|m| m.subcommand_matches(CMD_SILO_VENDOR))
+            .unwrap();
+        silo_vendor(vendor_matches, &Config::default()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(destination.join("node/cli/main.ts")).unwrap(),
+            "main"
+        );
+        assert!(!destination.join("go/api").exists());
+        assert_eq!(
+            fs::read_to_string(destination.join(SILO_METADATA_FILENAME)).unwrap(),
+            metadata
+        );
+
+        let manifest = VendorManifest::read(&destination).unwrap().unwrap();
+        assert_eq!(manifest.url, silo.to_str().unwrap());
+        assert_eq!(manifest.seeds.len(), 1);
+        assert_eq!(manifest.seeds[0].path, "node/cli");
+        assert!(destination.join(VENDOR_MANIFEST_FILENAME).is_file());
+    }
+}
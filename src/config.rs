@@ -1,8 +1,311 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, File},
+    io::BufReader,
+    path::PathBuf,
+};
 
-use anyhow::{Error, Ok};
+use anyhow::{Context, Error, Ok};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
 
-use crate::constants::CONFIG_DIR_NAME;
+use crate::{
+    archive::{is_remote, local_path},
+    constants::{
+        CONFIG_DIR_NAME, CONFIG_FILENAME, DEFAULT_SILO, DEFAULT_SILO_NAME, DEFAULT_SILO_REF,
+    },
+    git::GitBackendKind,
+};
+
+/// Local silos are trusted with their absolute paths. Otherwise trusting a relative
+/// path like `.` would trust whatever directory it points to in the later runs.
+fn trusted_silo_url(url: &str) -> String {
+    if is_remote(url) {
+        return url.to_string();
+    }
+
+    match fs::canonicalize(local_path(url)) {
+        Result::Ok(path) => path.display().to_string(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// A silo registered with a name
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiloConfig {
+    pub name: String,
+    pub url: String,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// Name of the environment variable that holds the access token of a private silo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    /// Access token of a private silo. `token_env` is preferred to keep it out of the config file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// URLs of the mirrors that are tried in order when the silo cannot be fetched from its URL
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+}
+
+impl SiloConfig {
+    /// Returns the access token from the environment variable if it is set, otherwise from the config
+    pub fn token(&self) -> Option<String> {
+        self.token_env
+            .as_ref()
+            .and_then(|name| env::var(name).ok())
+            .filter(|token| !token.is_empty())
+            .or(self.token.clone())
+    }
+}
+
+/// The silo that a command works on, resolved from the CLI arguments and the config
+#[derive(Debug, Clone)]
+pub struct ResolvedSilo {
+    /// Name of the silo if it is a registered one
+    pub name: Option<String>,
+    pub url: String,
+    pub reference: String,
+    /// Access token that is used for HTTPS clones
+    pub token: Option<String>,
+    pub mirrors: Vec<String>,
+}
+
+impl ResolvedSilo {
+    /// `--ref` value takes precedence over the one registered with the silo
+    fn registered(silo: &SiloConfig, reference: Option<&String>) -> ResolvedSilo {
+        ResolvedSilo {
+            name: Some(silo.name.clone()),
+            url: silo.url.clone(),
+            reference: reference
+                .or(silo.reference.as_ref())
+                .cloned()
+                .unwrap_or(DEFAULT_SILO_REF.to_string()),
+            token: silo.token(),
+            mirrors: silo.mirrors.clone(),
+        }
+    }
+
+    /// Name of the silo if it is a registered one, otherwise its URL
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.url)
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// The token is only sent to the URL that it is configured for, never to
+    /// the mirrors or to the URLs that the rewrite rules point to
+    pub fn token_for(&self, url: &str) -> Option<&str> {
+        self.token().filter(|_| url == self.url)
+    }
+}
+
+/// User configuration, stored as JSON inside the config directory
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Never touch the network, only use the cached or local silos
+    pub offline: bool,
+    /// Registered silos
+    pub silos: Vec<SiloConfig>,
+    /// Name of the silo that is used when no silo is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_silo: Option<String>,
+    /// Whether to use the in-process git implementation or the `git` binary
+    pub git_backend: GitBackendKind,
+    /// minisign public keys that the silo manifests are verified with
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_keys: Vec<String>,
+    /// Refuse the silos without a signed manifest
+    pub require_signed_silos: bool,
+    /// URL rewrite rules like the `insteadOf` of git. The URLs starting with a key
+    /// are rewritten to start with its value, the longest matching key wins.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub url_rewrites: BTreeMap<String, String>,
+    /// URLs of the unregistered silos that the user trusts to plant from
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trusted_silos: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            offline: false,
+            silos: vec![SiloConfig {
+                name: DEFAULT_SILO_NAME.to_string(),
+                url: DEFAULT_SILO.to_string(),
+                reference: None,
+                token_env: None,
+                token: None,
+                mirrors: vec![],
+            }],
+            default_silo: None,
+            git_backend: GitBackendKind::default(),
+            trusted_keys: vec![],
+            require_signed_silos: false,
+            url_rewrites: BTreeMap::new(),
+            trusted_silos: vec![],
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file. If there is no config file yet, returns the default config.
+    pub fn load() -> Result<Config, Error> {
+        let path = config_path()?.join(CONFIG_FILENAME);
+
+        if !path.is_file() {
+            return Ok(Config::default());
+        }
+
+        let reader = BufReader::new(File::open(&path)?);
+        let config = serde_json::from_reader(reader)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        Ok(config)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let path = config_path()?.join(CONFIG_FILENAME);
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to write config file {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self)?;
+
+        Ok(())
+    }
+
+    pub fn find_silo(&self, name: impl AsRef<str>) -> Option<&SiloConfig> {
+        self.silos.iter().find(|s| s.name == name.as_ref())
+    }
+
+    /// Registered silos are added by the user so they are trusted as well
+    pub fn is_trusted_silo(&self, silo: &ResolvedSilo) -> bool {
+        silo.name.is_some() || self.trusted_silos.contains(&trusted_silo_url(&silo.url))
+    }
+
+    /// Remembers the silo as trusted. The config needs to be saved afterwards.
+    pub fn trust_silo(&mut self, silo: &ResolvedSilo) {
+        let url = trusted_silo_url(&silo.url);
+        if !self.trusted_silos.contains(&url) {
+            self.trusted_silos.push(url);
+        }
+    }
+
+    /// Resolves the silo with the given URL. If a silo is registered
+    /// with the same URL, its token and mirrors are used as well.
+    pub fn silo_for_url(&self, url: impl AsRef<str>, reference: Option<&String>) -> ResolvedSilo {
+        let url = url.as_ref();
+
+        match self.silos.iter().find(|s| s.url == url) {
+            Some(silo) => ResolvedSilo::registered(silo, reference),
+            None => ResolvedSilo {
+                name: None,
+                url: url.to_string(),
+                reference: reference.cloned().unwrap_or(DEFAULT_SILO_REF.to_string()),
+                token: None,
+                mirrors: vec![],
+            },
+        }
+    }
+
+    /// Returns the silo that is set as default. If there is none, the first registered silo.
+    pub fn default_silo(&self) -> Option<&SiloConfig> {
+        self.default_silo
+            .as_ref()
+            .and_then(|name| self.find_silo(name))
+            .or(self.silos.first())
+    }
+
+    /// Resolves the silo from the `--silo` and `--ref` values. The silo can be either
+    /// a registered silo name or a URL/path. If not given, the default silo is used.
+    pub fn resolve_silo(
+        &self,
+        silo: Option<&String>,
+        reference: Option<&String>,
+    ) -> Result<ResolvedSilo, Error> {
+        match silo {
+            Some(silo) => match self.find_silo(silo) {
+                Some(s) => Ok(ResolvedSilo::registered(s, reference)),
+                None => Ok(self.silo_for_url(silo, reference)),
+            },
+            None => {
+                let s = self.default_silo().with_context(|| {
+                    "There is no silo configured. Either use \"--silo\" flag or add one with \"tohum silo add\""
+                })?;
+                Ok(ResolvedSilo::registered(s, reference))
+            }
+        }
+    }
+
+    /// Resolves all the silos that a command consults. If a silo is given via `--silo`
+    /// flag, only that one is used. Otherwise all the registered silos are used in order,
+    /// starting with the default one.
+    pub fn resolve_silos(
+        &self,
+        silo: Option<&String>,
+        reference: Option<&String>,
+    ) -> Result<Vec<ResolvedSilo>, Error> {
+        if silo.is_some() {
+            return Ok(vec![self.resolve_silo(silo, reference)?]);
+        }
+
+        let default_silo = self.default_silo().with_context(|| {
+            "There is no silo configured. Either use \"--silo\" flag or add one with \"tohum silo add\""
+        })?;
+
+        let mut silos = vec![ResolvedSilo::registered(default_silo, reference)];
+        for silo in &self.silos {
+            if silo.name != default_silo.name {
+                silos.push(ResolvedSilo::registered(silo, reference));
+            }
+        }
+
+        Ok(silos)
+    }
+
+    /// Resolves the silos to look for the given seed. A seed name can be qualified with
+    /// a registered silo name (e.g `my-silo:@node/cli-ts`) to pick the silo explicitly.
+    /// Returns the silos and the seed name without the qualifier.
+    pub fn resolve_seed_silos<'a>(
+        &self,
+        seed_name: &'a str,
+        silo: Option<&String>,
+        reference: Option<&String>,
+    ) -> Result<(Vec<ResolvedSilo>, &'a str), Error> {
+        match split_qualified_seed_name(seed_name) {
+            (Some(silo_name), seed_name) => {
+                let silo = self
+                    .find_silo(silo_name)
+                    .with_context(|| format!("Silo {} is not registered", silo_name.cyan()))?;
+                Ok((vec![ResolvedSilo::registered(silo, reference)], seed_name))
+            }
+            (None, seed_name) => Ok((self.resolve_silos(silo, reference)?, seed_name)),
+        }
+    }
+}
+
+/// Applies the longest matching rewrite rule to the URL
+pub fn rewrite_url(url: &str, rewrites: &BTreeMap<String, String>) -> String {
+    rewrites
+        .iter()
+        .filter(|(prefix, _)| url.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(prefix, replacement)| format!("{}{}", replacement, &url[prefix.len()..]))
+        .unwrap_or(url.to_string())
+}
+
+/// Splits a `silo-name:seed-name` formatted seed name
+fn split_qualified_seed_name(seed_name: &str) -> (Option<&str>, &str) {
+    match seed_name.split_once(':') {
+        Some((silo_name, name)) if !silo_name.is_empty() && !silo_name.starts_with('@') => {
+            (Some(silo_name), name)
+        }
+        _ => (None, seed_name),
+    }
+}
 
 pub fn config_path() -> Result<PathBuf, Error> {
     let os_config_dir = dirs::config_local_dir().unwrap();
@@ -15,3 +318,53 @@ pub fn config_path() -> Result<PathBuf, Error> {
 
     Ok(config_dir)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_with_longest_prefix() {
+        let rewrites = BTreeMap::from([
+            (
+                "https://github.com/".to_string(),
+                "https://git.example.com/github/".to_string(),
+            ),
+            (
+                "https://github.com/acme/".to_string(),
+                "git@git.example.com:acme/".to_string(),
+            ),
+        ]);
+
+        assert_eq!(
+            rewrite_url("https://github.com/user/silo.git", &rewrites),
+            "https://git.example.com/github/user/silo.git"
+        );
+        assert_eq!(
+            rewrite_url("https://github.com/acme/silo.git", &rewrites),
+            "git@git.example.com:acme/silo.git"
+        );
+        assert_eq!(
+            rewrite_url("https://gitlab.com/user/silo.git", &rewrites),
+            "https://gitlab.com/user/silo.git"
+        );
+    }
+
+    #[test]
+    fn sends_token_only_to_silo_url() {
+        let silo = ResolvedSilo {
+            name: None,
+            url: "https://github.com/acme/silo.git".to_string(),
+            reference: "main".to_string(),
+            token: Some("secret".to_string()),
+            mirrors: vec!["https://mirror.example.com/silo.git".to_string()],
+        };
+
+        assert_eq!(silo.token_for(&silo.url), Some("secret"));
+        assert_eq!(silo.token_for(&silo.mirrors[0]), None);
+        assert_eq!(
+            silo.token_for("https://git.example.com/github/acme/silo.git"),
+            None
+        );
+    }
+}
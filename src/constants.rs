@@ -1,5 +1,23 @@
 pub const TOHUMRC_FILENAME: &str = ".tohumrc";
 pub const TOHUMRC_GLOB_PATTERN: &str = "**/.tohumrc";
+pub const TOHUMRC_SCHEMA: &str = include_str!("../metadata.schema.json");
+/// Variables that tohum defines for every seed
+pub const BUILTIN_VARIABLES: [&str; 3] = ["project_name", "authors", "silo"];
 pub const CONFIG_DIR_NAME: &str = "tohum";
+pub const CONFIG_FILENAME: &str = "config.json";
+pub const DEFAULT_SILO_NAME: &str = "tohum";
 pub const DEFAULT_SILO: &str = "https://github.com/mkaramuk/tohum.git";
-pub const DEFAULT_SILO_BRANCH: &str = "main";
+pub const DEFAULT_SILO_REF: &str = "main";
+pub const CACHE_DIR_NAME: &str = "cache";
+pub const CACHE_REPO_DIR_NAME: &str = "repo";
+pub const CACHE_ENTRY_FILENAME: &str = "entry.json";
+pub const SILO_INDEX_FILENAME: &str = "tohum-index.json";
+pub const SILO_INDEX_PATTERN: &str = "/tohum-index.json";
+pub const SILO_INDEX_VERSION: u32 = 1;
+pub const SILO_MANIFEST_FILENAME: &str = "tohum-manifest.txt";
+pub const SILO_MANIFEST_SIGNATURE_FILENAME: &str = "tohum-manifest.txt.minisig";
+pub const SILO_MANIFEST_PATTERNS: [&str; 2] =
+    ["/tohum-manifest.txt", "/tohum-manifest.txt.minisig"];
+pub const VENDOR_MANIFEST_FILENAME: &str = "tohum-vendor.json";
+pub const SILO_METADATA_FILENAME: &str = "tohum-silo.json";
+pub const SILO_METADATA_PATTERN: &str = "/tohum-silo.json";
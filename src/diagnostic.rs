@@ -0,0 +1,70 @@
+use std::{fmt, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// Doesn't prevent the seed to be used, e.g an unused variable
+    Warning,
+}
+
+/// A problem found in a file of a silo, e.g a `.tohumrc` file that cannot be parsed
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub path: PathBuf,
+    /// 1-based line of the problem, if it is known
+    pub line: Option<usize>,
+    /// 1-based column of the problem, if it is known
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(path: impl Into<PathBuf>, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            path: path.into(),
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(path: impl Into<PathBuf>, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::new(path, message)
+        }
+    }
+
+    pub fn from_json_error(path: impl Into<PathBuf>, err: &serde_json::Error) -> Diagnostic {
+        // serde_json appends the location to the message, it is kept separately
+        let location = format!(" at line {} column {}", err.line(), err.column());
+        let message = err.to_string();
+        let message = message.strip_suffix(&location).unwrap_or(&message);
+
+        Diagnostic {
+            severity: Severity::Error,
+            path: path.into(),
+            // Errors that are not about the content (e.g I/O) have no location
+            line: (err.line() > 0).then_some(err.line()),
+            column: (err.column() > 0).then_some(err.column()),
+            message: message.to_string(),
+        }
+    }
+
+    /// Path of the file with the location of the problem, e.g `seed/.tohumrc:3:14`
+    pub fn location(&self) -> String {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", self.path.display(), line, column),
+            (Some(line), None) => format!("{}:{}", self.path.display(), line),
+            _ => self.path.display().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location(), self.message)
+    }
+}
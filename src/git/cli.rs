@@ -0,0 +1,206 @@
+use anyhow::Error;
+use anyhow::Result;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use std::path::Path;
+use std::process::Command;
+
+use crate::{git::GitBackend, process::check_exit_status};
+
+/// Git backend that shells out to the `git` binary. SSH remotes are handled
+/// by git itself so the SSH agent and `GIT_SSH_COMMAND` are honoured.
+pub struct CliBackend {
+    /// Access token that is sent to the HTTPS remotes
+    pub token: Option<String>,
+}
+
+impl CliBackend {
+    /// The token is passed via the environment so it is neither visible in
+    /// the process list nor stored in the config of the cloned repository.
+    fn git(&self) -> Command {
+        let mut command = Command::new("git");
+        if let Some(credentials) = self.encoded_credentials() {
+            command
+                .env("GIT_CONFIG_COUNT", "1")
+                .env("GIT_CONFIG_KEY_0", "http.extraHeader")
+                .env(
+                    "GIT_CONFIG_VALUE_0",
+                    format!("Authorization: Basic {}", credentials),
+                );
+        }
+
+        command
+    }
+
+    /// Credentials of the basic authentication, the token is used as the password
+    fn encoded_credentials(&self) -> Option<String> {
+        self.token
+            .as_ref()
+            .map(|token| BASE64_STANDARD.encode(format!("x-access-token:{}", token)))
+    }
+
+    /// Values that must not appear in the error messages
+    fn secrets(&self) -> Vec<String> {
+        self.token
+            .iter()
+            .cloned()
+            .chain(self.encoded_credentials())
+            .collect()
+    }
+}
+
+impl GitBackend for CliBackend {
+    /// Makes a sparse and blobless clone of the given reference
+    fn sparse_clone(
+        &self,
+        url: &str,
+        reference: &str,
+        glob_pattern: &Path,
+        output_path: &Path,
+    ) -> Result<(), Error> {
+        let output = self
+            .git()
+            .arg("init")
+            .arg("--quiet")
+            .arg(output_path)
+            .output()?;
+        check_exit_status(output, &self.secrets())?;
+
+        let output = self
+            .git()
+            .arg("remote")
+            .arg("add")
+            .arg("origin")
+            .arg(url)
+            .current_dir(output_path)
+            .output()?;
+        check_exit_status(output, &self.secrets())?;
+
+        // Mark the remote as a promisor so the missing blobs
+        // can be fetched later when the sparse-checkout is widened
+        for (key, value) in [
+            ("remote.origin.promisor", "true"),
+            ("remote.origin.partialclonefilter", "blob:none"),
+        ] {
+            let output = self
+                .git()
+                .arg("config")
+                .arg(key)
+                .arg(value)
+                .current_dir(output_path)
+                .output()?;
+            check_exit_status(output, &self.secrets())?;
+        }
+
+        let output = self
+            .git()
+            .arg("sparse-checkout")
+            .arg("init")
+            .arg("--no-cone")
+            .current_dir(output_path)
+            .output()?;
+        check_exit_status(output, &self.secrets())?;
+
+        let output = self
+            .git()
+            .arg("sparse-checkout")
+            .arg("set")
+            .arg(glob_pattern)
+            .current_dir(output_path)
+            .output()?;
+        check_exit_status(output, &self.secrets())?;
+
+        self.sparse_update(output_path, url, reference)
+    }
+
+    fn sparse_update(&self, repo_path: &Path, url: &str, reference: &str) -> Result<(), Error> {
+        let output = self
+            .git()
+            .arg("remote")
+            .arg("set-url")
+            .arg("origin")
+            .arg(url)
+            .current_dir(repo_path)
+            .output()?;
+        check_exit_status(output, &self.secrets())?;
+
+        let output = self
+            .git()
+            .arg("fetch")
+            .arg("--depth=1")
+            .arg("--filter=blob:none")
+            .arg("origin")
+            .arg(reference)
+            .current_dir(repo_path)
+            .output()?;
+        check_exit_status(output, &self.secrets())?;
+
+        let output = self
+            .git()
+            .arg("checkout")
+            .arg("--quiet")
+            .arg("--force")
+            .arg("--detach")
+            .arg("FETCH_HEAD")
+            .current_dir(repo_path)
+            .output()?;
+        check_exit_status(output, &self.secrets())?;
+
+        Ok(())
+    }
+
+    /// In offline mode git is not allowed to fetch the missing
+    /// objects from a remote other than the local file system.
+    fn sparse_add(
+        &self,
+        repo_path: &Path,
+        glob_pattern: &Path,
+        offline: bool,
+    ) -> Result<(), Error> {
+        // git doesn't check whether the pattern is already there
+        let output = self
+            .git()
+            .arg("sparse-checkout")
+            .arg("list")
+            .current_dir(repo_path)
+            .output()?;
+        let patterns = String::from_utf8_lossy(&output.stdout).into_owned();
+        check_exit_status(output, &self.secrets())?;
+
+        let pattern = glob_pattern.display().to_string();
+        if patterns.lines().any(|line| line.trim() == pattern) {
+            return Ok(());
+        }
+
+        let mut command = self.git();
+        if offline {
+            command
+                .arg("-c")
+                .arg("protocol.allow=never")
+                .arg("-c")
+                .arg("protocol.file.allow=always");
+        }
+
+        let output = command
+            .arg("sparse-checkout")
+            .arg("add")
+            .arg(glob_pattern)
+            .current_dir(repo_path)
+            .output()?;
+        check_exit_status(output, &self.secrets())?;
+
+        Ok(())
+    }
+
+    fn head_commit(&self, repo_path: &Path) -> Result<String, Error> {
+        let output = self
+            .git()
+            .arg("rev-parse")
+            .arg("HEAD")
+            .current_dir(repo_path)
+            .output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        check_exit_status(output, &self.secrets())?;
+
+        Ok(stdout)
+    }
+}
@@ -0,0 +1,310 @@
+mod cli;
+mod native;
+
+use std::{env, path::Path};
+
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+pub use cli::CliBackend;
+pub use native::NativeBackend;
+
+/// Git operations that are needed to fetch silos. All the clones are sparse,
+/// only the files matching with the sparse-checkout patterns are checked out.
+pub trait GitBackend {
+    /// Clones the given reference (branch, tag or commit SHA) and checks out
+    /// the files matching with the glob pattern
+    fn sparse_clone(
+        &self,
+        url: &str,
+        reference: &str,
+        glob_pattern: &Path,
+        output_path: &Path,
+    ) -> Result<(), Error>;
+
+    /// Fetches the given reference (branch, tag or commit SHA) into an existing sparse
+    /// clone and checks it out as a detached HEAD. The URL becomes the new origin of the
+    /// clone, so a silo can be fetched from one of its mirrors.
+    fn sparse_update(&self, repo_path: &Path, url: &str, reference: &str) -> Result<(), Error>;
+
+    /// Adds a new pattern to the sparse-checkout of an existing clone and checks
+    /// out the matching files. In offline mode the network must not be used.
+    fn sparse_add(&self, repo_path: &Path, glob_pattern: &Path, offline: bool)
+    -> Result<(), Error>;
+
+    /// Returns the SHA of the commit that is currently checked out
+    fn head_commit(&self, repo_path: &Path) -> Result<String, Error>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GitBackendKind {
+    /// In-process git implementation
+    #[default]
+    Native,
+    /// Uses the `git` binary
+    Cli,
+}
+
+impl GitBackendKind {
+    /// `token` is the access token of the silo that is sent to the HTTPS remotes
+    pub fn backend(self, token: Option<&str>) -> Box<dyn GitBackend> {
+        let token = token.map(str::to_string);
+
+        match self {
+            GitBackendKind::Native => Box::new(NativeBackend { token }),
+            GitBackendKind::Cli => Box::new(CliBackend { token }),
+        }
+    }
+
+    /// Returns the backend that can work with the given URL. libgit2 cannot run
+    /// the `GIT_SSH_COMMAND`, so SSH remotes are cloned with the `git` binary when it is set.
+    pub fn for_url(self, url: &str) -> GitBackendKind {
+        if self == GitBackendKind::Native
+            && is_ssh_url(url)
+            && env::var_os("GIT_SSH_COMMAND").is_some_and(|v| !v.is_empty())
+        {
+            return GitBackendKind::Cli;
+        }
+
+        self
+    }
+}
+
+/// Checks whether the given URL is an `ssh://` URL or an scp-like one (e.g `git@github.com:user/repo.git`)
+pub fn is_ssh_url(url: &str) -> bool {
+    if let Some((scheme, _)) = url.split_once("://") {
+        return scheme == "ssh" || scheme == "git+ssh";
+    }
+
+    match url.split_once(':') {
+        // `C:\path` is a Windows path, not a host
+        Some((host, _)) => host.len() > 1 && !host.contains('/'),
+        None => false,
+    }
+}
+
+/// Checks whether the given reference is a full commit SHA
+pub fn is_commit_sha(reference: impl AsRef<str>) -> bool {
+    let reference = reference.as_ref();
+    reference.len() == 40 && reference.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use git2::{Repository, Signature};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const BACKENDS: [GitBackendKind; 2] = [GitBackendKind::Native, GitBackendKind::Cli];
+
+    /// A bare repository with a `main` branch of two commits and
+    /// an annotated `v1` tag that points to the first one
+    struct Remote {
+        dir: TempDir,
+        work: Repository,
+        first: String,
+        second: String,
+    }
+
+    impl Remote {
+        fn new() -> Remote {
+            let dir = TempDir::new().unwrap();
+            Repository::init_bare(dir.path().join("silo.git")).unwrap();
+            let work = Repository::init(dir.path().join("work")).unwrap();
+
+            let mut remote = Remote {
+                dir,
+                work,
+                first: String::new(),
+                second: String::new(),
+            };
+            remote.first = remote.commit(&[
+                ("tohum-index.json", "{}"),
+                ("node/cli/.tohumrc", "{}"),
+                ("node/cli/src/main.ts", "first"),
+                ("go/api/.tohumrc", "{}"),
+                ("go/tohum-index.json", "{}"),
+            ]);
+            remote.tag_first();
+            remote.push();
+            remote.second = remote.commit(&[("node/cli/src/main.ts", "second")]);
+            remote.push();
+
+            remote
+        }
+
+        fn tag_first(&self) {
+            let first = self.work.revparse_single(&self.first).unwrap();
+            let signature = Signature::now("A", "a@example.com").unwrap();
+            self.work
+                .tag("v1", &first, &signature, "v1", false)
+                .unwrap();
+        }
+
+        fn url(&self) -> String {
+            self.dir.path().join("silo.git").display().to_string()
+        }
+
+        fn commit(&self, files: &[(&str, &str)]) -> String {
+            let root = self.work.workdir().unwrap();
+            let mut index = self.work.index().unwrap();
+            for (path, content) in files {
+                fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+                fs::write(root.join(path), content).unwrap();
+                index.add_path(Path::new(path)).unwrap();
+            }
+            index.write().unwrap();
+
+            let tree = self.work.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = Signature::now("A", "a@example.com").unwrap();
+            let parent = self
+                .work
+                .find_reference("refs/heads/main")
+                .ok()
+                .map(|r| r.peel_to_commit().unwrap());
+            let oid = self
+                .work
+                .commit(
+                    Some("refs/heads/main"),
+                    &signature,
+                    &signature,
+                    "commit",
+                    &tree,
+                    &parent.iter().collect::<Vec<_>>(),
+                )
+                .unwrap();
+
+            oid.to_string()
+        }
+
+        fn push(&self) {
+            let mut remote = self.work.remote_anonymous(&self.url()).unwrap();
+            remote
+                .push(
+                    &[
+                        "+refs/heads/main:refs/heads/main",
+                        "+refs/tags/v1:refs/tags/v1",
+                    ],
+                    None,
+                )
+                .unwrap();
+        }
+    }
+
+    fn clone(backend: GitBackendKind, remote: &Remote, reference: &str) -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let repo_path = dir.path().join("repo");
+        backend
+            .backend(None)
+            .sparse_clone(
+                &remote.url(),
+                reference,
+                Path::new("/tohum-index.json"),
+                &repo_path,
+            )
+            .unwrap_or_else(|e| panic!("{:?} {}: {:#}", backend, reference, e));
+
+        (dir, repo_path)
+    }
+
+    #[test]
+    fn clones_only_the_sparse_patterns() {
+        let remote = Remote::new();
+
+        for backend in BACKENDS {
+            for (reference, commit) in [
+                ("main", &remote.second),
+                ("v1", &remote.first),
+                (remote.first.as_str(), &remote.first),
+            ] {
+                let (_dir, repo_path) = clone(backend, &remote, reference);
+
+                assert!(repo_path.join("tohum-index.json").is_file());
+                assert!(!repo_path.join("node/cli/.tohumrc").exists());
+                // Patterns with a leading slash only match at the root
+                assert!(!repo_path.join("go/tohum-index.json").exists());
+                assert_eq!(
+                    &backend.backend(None).head_commit(&repo_path).unwrap(),
+                    commit,
+                    "{:?} {}",
+                    backend,
+                    reference
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn adds_sparse_patterns() {
+        let remote = Remote::new();
+
+        for backend in BACKENDS {
+            let (_dir, repo_path) = clone(backend, &remote, "v1");
+            let git = backend.backend(None);
+
+            git.sparse_add(&repo_path, Path::new("node/cli/*"), false)
+                .unwrap();
+            // Adding the same pattern again is a no-op
+            git.sparse_add(&repo_path, Path::new("node/cli/*"), false)
+                .unwrap();
+            assert_eq!(
+                fs::read_to_string(repo_path.join("node/cli/src/main.ts")).unwrap(),
+                "first",
+                "{:?}",
+                backend
+            );
+            assert!(!repo_path.join("go/api/.tohumrc").exists());
+
+            // The objects of a local remote can be fetched offline
+            git.sparse_add(&repo_path, Path::new("**/.tohumrc"), true)
+                .unwrap();
+            assert!(repo_path.join("go/api/.tohumrc").is_file(), "{:?}", backend);
+            assert!(repo_path.join("tohum-index.json").is_file());
+        }
+    }
+
+    #[test]
+    fn updates_to_another_reference() {
+        let remote = Remote::new();
+
+        for backend in BACKENDS {
+            let (_dir, repo_path) = clone(backend, &remote, "v1");
+            let git = backend.backend(None);
+            git.sparse_add(&repo_path, Path::new("node/cli/*"), false)
+                .unwrap();
+
+            git.sparse_update(&repo_path, &remote.url(), "main")
+                .unwrap();
+            assert_eq!(git.head_commit(&repo_path).unwrap(), remote.second);
+            assert_eq!(
+                fs::read_to_string(repo_path.join("node/cli/src/main.ts")).unwrap(),
+                "second",
+                "{:?}",
+                backend
+            );
+
+            git.sparse_update(&repo_path, &remote.url(), &remote.first)
+                .unwrap();
+            assert_eq!(git.head_commit(&repo_path).unwrap(), remote.first);
+            assert_eq!(
+                fs::read_to_string(repo_path.join("node/cli/src/main.ts")).unwrap(),
+                "first",
+                "{:?}",
+                backend
+            );
+        }
+    }
+
+    #[test]
+    fn detects_ssh_urls() {
+        assert!(is_ssh_url("git@github.com:user/repo.git"));
+        assert!(is_ssh_url("ssh://git@github.com/user/repo.git"));
+        assert!(!is_ssh_url("https://github.com/user/repo.git"));
+        assert!(!is_ssh_url("C:\\silos\\repo"));
+        assert!(!is_ssh_url("/tmp/silo.git"));
+    }
+}
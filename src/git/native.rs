@@ -0,0 +1,239 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error, Result, anyhow};
+use git2::{
+    Commit, Cred, CredentialType, Direction, FetchOptions, ObjectType, Oid, RemoteCallbacks,
+    Repository, TreeWalkMode, TreeWalkResult, build::CheckoutBuilder,
+};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::{
+    git::{GitBackend, is_commit_sha},
+    process::redact,
+};
+
+/// libgit2 keeps asking for credentials as long as they are rejected
+const MAX_CREDENTIAL_ATTEMPTS: usize = 3;
+
+/// In-process git backend. It doesn't support partial clones so the whole tree of
+/// the commit is fetched, but only the files matching with the sparse-checkout
+/// patterns are written to the working tree. The patterns are kept in the same
+/// file with the `git` binary, so both backends can work on the same clone.
+/// SSH remotes are authenticated with the keys of the SSH agent.
+pub struct NativeBackend {
+    /// Access token that is sent to the HTTPS remotes
+    pub token: Option<String>,
+}
+
+impl NativeBackend {
+    fn remote_callbacks(&self) -> RemoteCallbacks<'_> {
+        let mut callbacks = RemoteCallbacks::new();
+        let mut attempts = 0;
+
+        callbacks.credentials(move |url, username, allowed| {
+            attempts += 1;
+            if attempts > MAX_CREDENTIAL_ATTEMPTS {
+                return Err(git2::Error::from_str("authentication failed"));
+            }
+
+            let username = username.unwrap_or("git");
+            if allowed.contains(CredentialType::USERNAME) {
+                Cred::username(username)
+            } else if allowed.contains(CredentialType::SSH_KEY) {
+                Cred::ssh_key_from_agent(username)
+            } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+                match &self.token {
+                    Some(token) => Cred::userpass_plaintext("x-access-token", token),
+                    // Fall back to the credential helpers of the git config
+                    None => Cred::credential_helper(&git2::Config::open_default()?, url, None),
+                }
+            } else {
+                Cred::default()
+            }
+        });
+
+        callbacks
+    }
+
+    /// Values that must not appear in the error messages
+    fn secrets(&self) -> Vec<String> {
+        self.token.iter().cloned().collect()
+    }
+
+    fn fetch(&self, repo_path: &Path, url: &str, reference: &str) -> Result<(), Error> {
+        let repo = Repository::open(repo_path)?;
+        repo.remote_set_url("origin", url)?;
+        let mut remote = repo.find_remote("origin")?;
+
+        // Find out what the reference is, so only that one is fetched
+        let (refspec, oid) = if is_commit_sha(reference) {
+            (reference.to_string(), Oid::from_str(reference)?)
+        } else {
+            let connection =
+                remote.connect_auth(Direction::Fetch, Some(self.remote_callbacks()), None)?;
+            connection
+                .list()?
+                .iter()
+                .find(|head| {
+                    head.name() == reference
+                        || head.name() == format!("refs/heads/{}", reference)
+                        || head.name() == format!("refs/tags/{}", reference)
+                })
+                .map(|head| (head.name().to_string(), head.oid()))
+                .with_context(|| format!("couldn't find remote ref {}", reference))?
+        };
+
+        // Local transport of libgit2 doesn't support shallow fetches
+        let url = remote.url().unwrap_or_default();
+        let is_local = url.starts_with("file://") || Path::new(url).exists();
+
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(self.remote_callbacks());
+        if !is_local {
+            fetch_options.depth(1);
+        }
+        remote.fetch(&[&refspec], Some(&mut fetch_options), None)?;
+
+        // Annotated tags are pointing to a tag object
+        let commit = repo.find_object(oid, None)?.peel_to_commit()?;
+        checkout_sparse(&repo, &commit)?;
+        repo.set_head_detached(commit.id())?;
+
+        Ok(())
+    }
+}
+
+impl GitBackend for NativeBackend {
+    fn sparse_clone(
+        &self,
+        url: &str,
+        reference: &str,
+        glob_pattern: &Path,
+        output_path: &Path,
+    ) -> Result<(), Error> {
+        let repo = Repository::init(output_path)?;
+        repo.remote("origin", url)?;
+        repo.config()?.set_bool("core.sparseCheckout", true)?;
+
+        let sparse_path = sparse_checkout_path(&repo);
+        fs::create_dir_all(sparse_path.parent().unwrap())?;
+        fs::write(&sparse_path, format!("{}\n", glob_pattern.display()))?;
+
+        self.sparse_update(output_path, url, reference)
+    }
+
+    fn sparse_update(&self, repo_path: &Path, url: &str, reference: &str) -> Result<(), Error> {
+        self.fetch(repo_path, url, reference)
+            .map_err(|err| Error::msg(redact(&format!("{:#}", err), &self.secrets())))
+    }
+
+    /// All the objects are already fetched so the network is never used
+    fn sparse_add(
+        &self,
+        repo_path: &Path,
+        glob_pattern: &Path,
+        _offline: bool,
+    ) -> Result<(), Error> {
+        let repo = Repository::open(repo_path)?;
+        let sparse_path = sparse_checkout_path(&repo);
+        let previous_patterns = fs::read_to_string(&sparse_path).unwrap_or_default();
+        let pattern = glob_pattern.display().to_string();
+        if previous_patterns.lines().any(|line| line.trim() == pattern) {
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&sparse_path)?;
+        writeln!(file, "{}", pattern)?;
+
+        let commit = repo.head()?.peel_to_commit()?;
+        if let Err(err) = checkout_sparse(&repo, &commit) {
+            // Roll back to the previous patterns like git does
+            fs::write(&sparse_path, previous_patterns)?;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    fn head_commit(&self, repo_path: &Path) -> Result<String, Error> {
+        let repo = Repository::open(repo_path)?;
+        let commit = repo.head()?.peel_to_commit()?;
+
+        Ok(commit.id().to_string())
+    }
+}
+
+fn sparse_checkout_path(repo: &Repository) -> PathBuf {
+    repo.path().join("info").join("sparse-checkout")
+}
+
+/// Builds a matcher from the sparse-checkout patterns. Only a subset of the
+/// gitignore style patterns that tohum uses are supported.
+fn sparse_patterns(repo: &Repository) -> Result<GlobSet, Error> {
+    let content = fs::read_to_string(sparse_checkout_path(repo))
+        .context("Failed to read the sparse-checkout patterns")?;
+    let mut builder = GlobSetBuilder::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Leading slash anchors the pattern to the root which is already
+        // the case for the globs. A pattern that matches with a directory
+        // also matches with everything inside it.
+        let pattern = line.trim_start_matches('/');
+        for glob in [pattern.to_string(), format!("{}/**", pattern)] {
+            builder.add(GlobBuilder::new(&glob).literal_separator(false).build()?);
+        }
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Checks out the files of the commit that match with the sparse-checkout patterns.
+/// The files that were checked out before but don't exist in the commit are removed.
+fn checkout_sparse(repo: &Repository, commit: &Commit) -> Result<(), Error> {
+    let patterns = sparse_patterns(repo)?;
+    let mut paths = vec![];
+
+    commit.tree()?.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(ObjectType::Blob) {
+            let path = format!("{}{}", root, entry.name().unwrap_or_default());
+            if patterns.is_match(&path) {
+                paths.push(path);
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+
+    for entry in repo.index()?.iter() {
+        let path = String::from_utf8_lossy(&entry.path).into_owned();
+        if patterns.is_match(&path) && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    // An empty path list means everything for libgit2
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force().disable_pathspec_match(true);
+    for path in &paths {
+        checkout.path(path);
+    }
+
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout))
+        .map_err(|e| anyhow!("Failed to check out {}: {}", commit.id(), e.message()))?;
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{SILO_INDEX_FILENAME, SILO_INDEX_VERSION, TOHUMRC_FILENAME},
+    diagnostic::Diagnostic,
+    io::is_contained_path,
+    seed::Seed,
+};
+
+/// Metadata of all the seeds of a silo, stored at the silo root. When a git or
+/// archive silo has an index, only that file is read to list the seeds.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SiloIndex {
+    /// Version of the index format
+    pub version: u32,
+    pub seeds: Vec<IndexedSeed>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IndexedSeed {
+    /// Path of the seed directory, relative to the silo root
+    pub path: String,
+    #[serde(flatten)]
+    pub seed: Seed,
+}
+
+impl SiloIndex {
+    /// Builds the index from the seeds found by walking the `.tohumrc` files of the silo
+    pub fn new(seeds: Vec<Seed>) -> SiloIndex {
+        let mut seeds = seeds
+            .into_iter()
+            .map(|seed| IndexedSeed {
+                path: seed.repo_path.clone(),
+                seed,
+            })
+            .collect::<Vec<_>>();
+        seeds.sort_by(|a, b| a.path.cmp(&b.path));
+
+        SiloIndex {
+            version: SILO_INDEX_VERSION,
+            seeds,
+        }
+    }
+
+    /// Reads the index of the silo. Returns `None` if the silo doesn't have one.
+    pub fn read(silo_path: impl AsRef<Path>) -> Result<Option<SiloIndex>, Error> {
+        let path = silo_path.as_ref().join(SILO_INDEX_FILENAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let reader = BufReader::new(File::open(&path)?);
+        let index: SiloIndex = serde_json::from_reader(reader)
+            .with_context(|| format!("Invalid silo index {}", path.display()))?;
+
+        if index.version > SILO_INDEX_VERSION {
+            return Err(anyhow!(
+                "Silo index {} is built by a newer version of tohum. Please upgrade tohum",
+                path.display()
+            ));
+        }
+
+        // The seeds are checked out and copied from these paths
+        if let Some(indexed) = index.seeds.iter().find(|s| !is_contained_path(&s.path)) {
+            return Err(anyhow!(
+                "Silo index {} has an invalid seed path {}. Seed paths must be inside the silo",
+                path.display(),
+                indexed.path
+            ));
+        }
+
+        Ok(Some(index))
+    }
+
+    /// Writes the index to the silo root and returns its path
+    pub fn write(&self, silo_path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        let path = silo_path.as_ref().join(SILO_INDEX_FILENAME);
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to write silo index {}", path.display()))?;
+
+        // Going through a JSON value sorts the keys, so the output is stable
+        serde_json::to_writer_pretty(file, &serde_json::to_value(self)?)?;
+
+        Ok(path)
+    }
+
+    /// Returns the seeds of the index. The invalid ones are skipped and returned as
+    /// diagnostics of their `.tohumrc` files, the same as the walked seeds.
+    pub fn into_seeds(self) -> (Vec<Seed>, Vec<Diagnostic>) {
+        let mut seeds = vec![];
+        let mut diagnostics = vec![];
+
+        for indexed in self.seeds {
+            if let Some(problem) = indexed.seed.problem() {
+                diagnostics.push(Diagnostic::new(
+                    Path::new(&indexed.path).join(TOHUMRC_FILENAME),
+                    problem,
+                ));
+                continue;
+            }
+
+            seeds.push(Seed {
+                repo_path: indexed.path,
+                ..indexed.seed
+            });
+        }
+
+        (seeds, diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn indexed_seed(seed_path: &str) -> serde_json::Value {
+        serde_json::json!({
+            "path": seed_path,
+            "name": "@x/seed",
+            "version": "1.0.0",
+            "authors": [{ "name": "A" }],
+        })
+    }
+
+    fn write_index(seeds: Vec<serde_json::Value>) -> TempDir {
+        let silo = TempDir::new().unwrap();
+        let index = serde_json::json!({
+            "version": SILO_INDEX_VERSION,
+            "seeds": seeds,
+        });
+        fs::write(silo.path().join(SILO_INDEX_FILENAME), index.to_string()).unwrap();
+
+        silo
+    }
+
+    #[test]
+    fn reads_seed_paths_inside_the_silo() {
+        let silo = write_index(vec![indexed_seed("node/cli-ts")]);
+        let (seeds, diagnostics) = SiloIndex::read(silo.path()).unwrap().unwrap().into_seeds();
+
+        assert_eq!(seeds[0].repo_path, "node/cli-ts");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn rejects_seed_paths_outside_the_silo() {
+        for seed_path in ["/tmp/secret", "../secret", "node/../../secret"] {
+            let silo = write_index(vec![indexed_seed(seed_path)]);
+            let err = SiloIndex::read(silo.path()).unwrap_err();
+
+            assert!(
+                err.to_string().contains("invalid seed path"),
+                "{}: {}",
+                seed_path,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn skips_seeds_without_authors() {
+        let mut no_authors = indexed_seed("go/cli");
+        no_authors["authors"] = serde_json::json!([]);
+        let silo = write_index(vec![indexed_seed("node/cli-ts"), no_authors]);
+        let (seeds, diagnostics) = SiloIndex::read(silo.path()).unwrap().unwrap().into_seeds();
+
+        assert_eq!(seeds.len(), 1);
+        assert_eq!(seeds[0].repo_path, "node/cli-ts");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "go/cli/.tohumrc: Seed must include at least one author"
+        );
+    }
+}
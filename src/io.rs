@@ -2,9 +2,10 @@ use anyhow::{Context, Error};
 use std::{
     fs::{self, File},
     io::Read,
-    path::Path,
+    path::{Component, Path},
 };
 
+/// Copies the directory with all of its content except the `.git` directories
 pub fn copy_dir_recursive(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<(), Error> {
     let src = src.as_ref();
     let dst = dst.as_ref();
@@ -18,12 +19,18 @@ pub fn copy_dir_recursive(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Resul
         fs::read_dir(src).with_context(|| format!("Failed to read directory: {}", src.display()))?
     {
         let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+
         let file_type = entry.file_type()?;
         let src_path = entry.path();
         let dst_path = dst.join(entry.file_name());
 
         if file_type.is_dir() {
             copy_dir_recursive(&src_path, &dst_path)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&src_path, &dst_path)?;
         } else {
             fs::copy(&src_path, &dst_path).with_context(|| {
                 format!("Failed to copy file from {:?} to {:?}", src_path, dst_path)
@@ -34,6 +41,24 @@ pub fn copy_dir_recursive(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Resul
     Ok(())
 }
 
+/// Recreates the symlink with the same target. Following it would copy whatever it
+/// points to, e.g a seed symlink to `~/.ssh/id_rsa` would copy the key into the project.
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dst: &Path) -> Result<(), Error> {
+    let target =
+        fs::read_link(src).with_context(|| format!("Failed to read symlink {}", src.display()))?;
+    std::os::unix::fs::symlink(&target, dst)
+        .with_context(|| format!("Failed to create symlink {}", dst.display()))
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(src: &Path, _dst: &Path) -> Result<(), Error> {
+    Err(anyhow::anyhow!(
+        "{} is a symlink, symlinks in seeds are only supported on Unix",
+        src.display()
+    ))
+}
+
 /// Reads first 1 KB of the given file to detect
 /// whether or not it is a binary or plain text file
 pub fn is_binary(path: impl AsRef<Path>) -> bool {
@@ -48,3 +73,48 @@ pub fn is_binary(path: impl AsRef<Path>) -> bool {
     // İlk 1024 byte içinde Null Byte varsa büyük ihtimalle binary'dir
     buffer[..n].contains(&0)
 }
+
+/// Checks that the path stays inside the directory that it is joined to:
+/// it is relative and doesn't go up with `..`
+pub fn is_contained_path(path: impl AsRef<Path>) -> bool {
+    path.as_ref()
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn copies_symlinks_without_following() {
+        let dir = TempDir::new().unwrap();
+        let seed = dir.path().join("seed");
+        fs::create_dir_all(seed.join(".git")).unwrap();
+        fs::write(seed.join("README.md"), "readme").unwrap();
+        fs::write(dir.path().join("id_rsa"), "secret").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("id_rsa"), seed.join("key")).unwrap();
+        std::os::unix::fs::symlink("README.md", seed.join("docs.md")).unwrap();
+
+        let project = dir.path().join("project");
+        copy_dir_recursive(&seed, &project).unwrap();
+
+        assert!(!project.join(".git").exists());
+        assert_eq!(
+            fs::read_to_string(project.join("README.md")).unwrap(),
+            "readme"
+        );
+        assert!(project.join("key").is_symlink());
+        assert_eq!(
+            fs::read_link(project.join("key")).unwrap(),
+            dir.path().join("id_rsa")
+        );
+        assert_eq!(
+            fs::read_link(project.join("docs.md")).unwrap(),
+            Path::new("README.md")
+        );
+    }
+}
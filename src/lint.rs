@@ -0,0 +1,436 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error, Result};
+use walkdir::WalkDir;
+
+use crate::{
+    constants::{BUILTIN_VARIABLES, TOHUMRC_FILENAME, TOHUMRC_SCHEMA},
+    diagnostic::Diagnostic,
+    io::is_binary,
+    seed::{Seed, VariableType, compile_pattern},
+    template::{context_variables, dependency_order, new_template},
+};
+
+/// Checks the seed inside the given directory for the common mistakes: a `.tohumrc`
+/// file that doesn't match with the schema, variables that are used but not declared
+/// (or declared but never used), defaults that don't match with their types and
+/// templates that cannot be parsed.
+pub fn lint_seed(seed_path: impl AsRef<Path>) -> Result<Vec<Diagnostic>, Error> {
+    let seed_path = seed_path.as_ref();
+    let mut diagnostics = vec![];
+
+    let Some(seed) = lint_metadata(seed_path, &mut diagnostics)? else {
+        return Ok(diagnostics);
+    };
+
+    // Variable name -> location of its first usage
+    let mut used_variables = BTreeMap::new();
+
+    // Computed default values and conditions use the other variables as well
+    match seed.variable_dependencies() {
+        Ok(dependencies) => {
+            for variable in dependencies.values().flatten() {
+                used_variables
+                    .entry(variable.clone())
+                    .or_insert_with(|| PathBuf::from(TOHUMRC_FILENAME));
+            }
+            if let Err(err) = dependency_order(&dependencies) {
+                diagnostics.push(Diagnostic::new(TOHUMRC_FILENAME, err.to_string()));
+            }
+        }
+        Err(err) => {
+            // Locations inside the conditions and the default values are not useful
+            let message = match err.downcast_ref::<tera::Error>() {
+                Some(tera_err) => format!("{}: {}", err, template_message(tera_err)),
+                None => format!("{:#}", err),
+            };
+            diagnostics.push(Diagnostic::new(TOHUMRC_FILENAME, message));
+        }
+    }
+
+    let walker = WalkDir::new(seed_path)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(Result::ok);
+
+    for entry in walker {
+        let path = entry.path();
+        let relative_path = path.strip_prefix(seed_path)?;
+        if !path.is_file() || relative_path == Path::new(TOHUMRC_FILENAME) || is_binary(path) {
+            continue;
+        }
+
+        // Not a text file, so it is copied as is
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+
+        let name = relative_path.display().to_string();
+        let tera = match new_template(&name, &content) {
+            Ok(tera) => tera,
+            Err(err) => {
+                diagnostics.push(template_diagnostic(relative_path, &err));
+                continue;
+            }
+        };
+
+        for variable in context_variables(&tera, &name)? {
+            used_variables
+                .entry(variable.clone())
+                .or_insert_with(|| relative_path.to_path_buf());
+        }
+    }
+
+    let declared_variables = seed.variables.unwrap_or_default();
+    for (variable, path) in &used_variables {
+        if !declared_variables.contains_key(variable)
+            && !BUILTIN_VARIABLES.contains(&variable.as_str())
+        {
+            diagnostics.push(Diagnostic::new(
+                path,
+                format!(
+                    "Variable \"{}\" is used but not declared in \"variables\"",
+                    variable
+                ),
+            ));
+        }
+    }
+
+    let mut declared_names = declared_variables.keys().collect::<Vec<_>>();
+    declared_names.sort();
+    for name in declared_names {
+        if !used_variables.contains_key(name) {
+            diagnostics.push(Diagnostic::warning(
+                TOHUMRC_FILENAME,
+                format!("Variable \"{}\" is declared but never used", name),
+            ));
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// Validates the `.tohumrc` file of the seed and returns the seed if it can be read
+fn lint_metadata(seed_path: &Path, diagnostics: &mut Vec<Diagnostic>) -> Result<Option<Seed>> {
+    let path = seed_path.join(TOHUMRC_FILENAME);
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let value: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(err) => {
+            diagnostics.push(Diagnostic::from_json_error(TOHUMRC_FILENAME, &err));
+            return Ok(None);
+        }
+    };
+
+    let schema = serde_json::from_str(TOHUMRC_SCHEMA)?;
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|err| Error::msg(err.to_string()))
+        .context("Invalid seed metadata schema")?;
+    let schema_errors = validator
+        .iter_errors(&value)
+        .map(|err| {
+            let location = err.instance_path().to_string();
+            let message = if location.is_empty() {
+                err.to_string()
+            } else {
+                format!("{}: {}", location, err)
+            };
+            Diagnostic::new(TOHUMRC_FILENAME, message)
+        })
+        .collect::<Vec<_>>();
+    let is_schema_valid = schema_errors.is_empty();
+    diagnostics.extend(schema_errors);
+
+    let seed: Seed = match serde_json::from_value(value) {
+        Ok(seed) => seed,
+        Err(err) => {
+            // The schema errors already tell what is wrong
+            if is_schema_valid {
+                diagnostics.push(Diagnostic::new(TOHUMRC_FILENAME, err.to_string()));
+            }
+            return Ok(None);
+        }
+    };
+
+    // The schema checks the types of the defaults as well
+    if is_schema_valid && let Some(variables) = &seed.variables {
+        let mut names = variables.keys().collect::<Vec<_>>();
+        names.sort();
+
+        for name in names {
+            let variable = &variables[name];
+            let is_pattern_valid = match variable.pattern.as_deref().map(compile_pattern) {
+                Some(Err(err)) => {
+                    diagnostics.push(Diagnostic::new(
+                        TOHUMRC_FILENAME,
+                        format!("Variable \"{}\" has an {}", name, err),
+                    ));
+                    false
+                }
+                _ => true,
+            };
+
+            // Computed default values are known only while planting
+            let default = variable
+                .default
+                .as_ref()
+                .filter(|_| variable.computed_default().is_none());
+            if let Some(default) = default
+                && !variable.var_type.matches(default)
+            {
+                diagnostics.push(Diagnostic::new(
+                    TOHUMRC_FILENAME,
+                    format!(
+                        "Default value of variable \"{}\" is not a {}",
+                        name, variable.var_type
+                    ),
+                ));
+            } else if let Some(default) = default
+                // An invalid pattern is already reported
+                && is_pattern_valid
+                && let Err(err) = variable.validate(default)
+            {
+                diagnostics.push(Diagnostic::new(
+                    TOHUMRC_FILENAME,
+                    format!("Default value of variable \"{}\": {}", name, err),
+                ));
+            }
+
+            let is_text = matches!(variable.var_type, VariableType::String | VariableType::List);
+            let is_number = matches!(
+                variable.var_type,
+                VariableType::Number | VariableType::Integer
+            );
+            // Constraints that don't apply to the type are ignored while planting
+            let misplaced_constraints = [
+                ("pattern", variable.pattern.is_some() && !is_text),
+                ("min_length", variable.min_length.is_some() && !is_text),
+                ("max_length", variable.max_length.is_some() && !is_text),
+                ("minimum", variable.minimum.is_some() && !is_number),
+                ("maximum", variable.maximum.is_some() && !is_number),
+            ];
+            for (constraint, _) in misplaced_constraints.iter().filter(|(_, is_set)| *is_set) {
+                diagnostics.push(Diagnostic::new(
+                    TOHUMRC_FILENAME,
+                    format!(
+                        "\"{}\" doesn't apply to variable \"{}\" of type {}",
+                        constraint, name, variable.var_type
+                    ),
+                ));
+            }
+            // Choices of a list are its items, which are strings
+            let choice_type = match variable.var_type {
+                VariableType::List => VariableType::String,
+                var_type => var_type,
+            };
+            for choice in variable.choices.iter().flatten() {
+                if !choice_type.matches(choice) {
+                    diagnostics.push(Diagnostic::new(
+                        TOHUMRC_FILENAME,
+                        format!(
+                            "Choice {} of variable \"{}\" is not a {}",
+                            choice, name, choice_type
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(Some(seed))
+}
+
+/// Tera reports the location of a parse error inside the message
+/// of its source error, e.g ` --> 3:5`
+fn template_diagnostic(path: &Path, err: &tera::Error) -> Diagnostic {
+    let source = template_source(err);
+    let mut diagnostic = Diagnostic::new(
+        path,
+        format!("Template cannot be parsed: {}", template_message(err)),
+    );
+
+    let location = source
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("--> "))
+        .and_then(|location| location.split_once(':'));
+    if let Some((line, column)) = location {
+        diagnostic.line = line.parse().ok();
+        diagnostic.column = column.parse().ok();
+    }
+
+    diagnostic
+}
+
+fn template_source(err: &tera::Error) -> String {
+    std::error::Error::source(err)
+        .map(|source| source.to_string())
+        .unwrap_or_else(|| err.to_string())
+}
+
+/// The last line of a parse error tells what is expected, e.g `= expected an expression`
+fn template_message(err: &tera::Error) -> String {
+    let source = template_source(err);
+    source
+        .lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("= "))
+        .unwrap_or(source.trim())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::diagnostic::Severity;
+
+    /// Writes a seed with the given `.tohumrc` content and files, then lints it
+    fn lint(tohumrc: &str, files: &[(&str, &str)]) -> Vec<String> {
+        let seed = TempDir::new().unwrap();
+        fs::write(seed.path().join(TOHUMRC_FILENAME), tohumrc).unwrap();
+        for (path, content) in files {
+            let path = seed.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        lint_seed(seed.path())
+            .unwrap()
+            .iter()
+            .map(|d| match d.severity {
+                Severity::Error => format!("error {}", d),
+                Severity::Warning => format!("warning {}", d),
+            })
+            .collect()
+    }
+
+    fn tohumrc(variables: serde_json::Value) -> String {
+        serde_json::json!({
+            "name": "@x/seed",
+            "version": "1.0.0",
+            "authors": [{ "name": "A" }],
+            "variables": variables,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn accepts_valid_seed() {
+        let tohumrc = tohumrc(serde_json::json!({
+            "org": { "type": "string", "description": "Org", "default": "acme" },
+            "image": {
+                "type": "string",
+                "description": "Image",
+                "default": "{{ org }}/{{ project_name }}",
+                "computed": true,
+            },
+        }));
+
+        assert!(lint(&tohumrc, &[("README.md", "# {{ image }}")]).is_empty());
+    }
+
+    #[test]
+    fn reports_undeclared_and_unused_variables() {
+        let tohumrc = tohumrc(serde_json::json!({
+            "org": { "type": "string", "description": "Org", "default": "acme" },
+            "unused": { "type": "string", "description": "Unused" },
+        }));
+        let files = [
+            ("README.md", "{{ org }} {{ project_name }}"),
+            ("src/main.rs", "{{ undeclared }}"),
+        ];
+
+        assert_eq!(
+            lint(&tohumrc, &files),
+            [
+                "error src/main.rs: Variable \"undeclared\" is used but not declared in \"variables\"",
+                "warning .tohumrc: Variable \"unused\" is declared but never used",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_defaults_and_choices() {
+        let tohumrc = tohumrc(serde_json::json!({
+            "port": {
+                "type": "integer",
+                "description": "Port",
+                "default": 80,
+                "minimum": 1024,
+                "pattern": "^a",
+            },
+            "db": { "type": "string", "description": "DB", "choices": ["pg", 1], "default": "pg" },
+            "name": { "type": "string", "description": "Name", "default": "x", "pattern": "(" },
+        }));
+
+        assert_eq!(
+            lint(&tohumrc, &[("README.md", "{{ port }} {{ db }} {{ name }}")]),
+            [
+                "error .tohumrc: Choice 1 of variable \"db\" is not a string",
+                "error .tohumrc: Variable \"name\" has an invalid pattern (: unclosed group",
+                "error .tohumrc: Default value of variable \"port\": must be at least 1024",
+                "error .tohumrc: \"pattern\" doesn't apply to variable \"port\" of type integer",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_schema_errors() {
+        let tohumrc = tohumrc(serde_json::json!({
+            "port": { "type": "integer", "description": "Port", "default": "x" },
+        }));
+
+        assert_eq!(
+            lint(&tohumrc, &[("README.md", "{{ port }}")]),
+            ["error .tohumrc: /variables/port/default: \"x\" is not of type \"integer\""]
+        );
+    }
+
+    #[test]
+    fn reports_dependency_cycle() {
+        let tohumrc = tohumrc(serde_json::json!({
+            "a": { "type": "string", "description": "A", "default": "{{ b }}", "computed": true },
+            "b": { "type": "string", "description": "B", "default": "{{ a }}", "computed": true },
+        }));
+
+        assert_eq!(
+            lint(&tohumrc, &[]),
+            [
+                "error .tohumrc: Default values or conditions of the variables depend on each other: a -> b -> a"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_template_parse_error_location() {
+        let tohumrc = tohumrc(serde_json::json!({}));
+        let diagnostics = lint(
+            &tohumrc,
+            &[("src/main.rs", "fn main() {\n    {{ project_name | }}\n}\n")],
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        assert!(
+            diagnostics[0].starts_with("error src/main.rs:2:23: Template cannot be parsed: "),
+            "{}",
+            diagnostics[0]
+        );
+    }
+
+    #[test]
+    fn reports_json_error_location() {
+        let tohumrc = "{\n  \"name\": \"@x/seed\",\n  \"version\": 1.0.0\n}";
+
+        assert_eq!(
+            lint(tohumrc, &[]),
+            ["error .tohumrc:3:17: expected `,` or `}`"]
+        );
+    }
+}
@@ -1,29 +1,52 @@
+mod archive;
+mod cache;
 mod cmd;
 mod commands;
 mod config;
 mod constants;
+mod diagnostic;
 mod git;
+mod index;
 mod io;
+mod lint;
 mod macros;
+mod manifest;
 mod process;
 mod progress;
 mod seed;
 mod silo;
+mod template;
+mod vendor;
 
 use crate::{
-    cmd::{CMD_PLANT, CMD_SILO, CMD_SILO_INSPECT, CMD_SILO_LIST},
+    cmd::{
+        CMD_PLANT, CMD_SEED, CMD_SEED_LINT, CMD_SILO, CMD_SILO_ADD, CMD_SILO_CACHE,
+        CMD_SILO_CACHE_CLEAR, CMD_SILO_CACHE_LIST, CMD_SILO_CACHE_REFRESH, CMD_SILO_INDEX,
+        CMD_SILO_INDEX_BUILD, CMD_SILO_INFO, CMD_SILO_INSPECT, CMD_SILO_LIST, CMD_SILO_MANIFEST,
+        CMD_SILO_MANIFEST_BUILD, CMD_SILO_REMOVE, CMD_SILO_SET_DEFAULT, CMD_SILO_SOURCES,
+        CMD_SILO_VENDOR,
+    },
     commands::{
+        cache::{cache_clear, cache_list, cache_refresh},
+        index::index_build,
+        manifest::manifest_build,
         plant::plant_seed,
-        silo::{silo_inspect, silo_list},
+        seed::seed_lint,
+        silo::{silo_info, silo_inspect, silo_list},
+        sources::{silo_add, silo_remove, silo_set_default, silo_sources},
+        vendor::silo_vendor,
     },
+    config::Config,
 };
 use anyhow::{Error, Result};
 use colored::*;
 
-#[tokio::main]
-async fn main() -> Result<(), Error> {
+fn main() -> Result<(), Error> {
     if let Err(err) = run() {
         eprintln!("{}: {}", "Error".red().bold(), err);
+        for cause in err.chain().skip(1) {
+            eprintln!("  {} {}", "╰─>".red(), cause.to_string().trim());
+        }
         std::process::exit(1);
     }
 
@@ -31,18 +54,64 @@ async fn main() -> Result<(), Error> {
 }
 
 fn run() -> Result<(), Error> {
-    let _config_path = config::config_path()?;
+    let mut config = Config::load()?;
     let command = cmd::build_cmd();
     let cmd_matches = command.get_matches();
 
     if let Some(silo_matches) = cmd_matches.subcommand_matches(CMD_SILO) {
         if let Some(list_matches) = silo_matches.subcommand_matches(CMD_SILO_LIST) {
-            silo_list(list_matches)?
+            silo_list(list_matches, &config)?
+        } else if let Some(info_matches) = silo_matches.subcommand_matches(CMD_SILO_INFO) {
+            silo_info(info_matches, &config)?
         } else if let Some(inspect_matches) = silo_matches.subcommand_matches(CMD_SILO_INSPECT) {
-            silo_inspect(inspect_matches)?
+            silo_inspect(inspect_matches, &config)?
+        } else if let Some(add_matches) = silo_matches.subcommand_matches(CMD_SILO_ADD) {
+            silo_add(add_matches, &mut config)?
+        } else if let Some(remove_matches) = silo_matches.subcommand_matches(CMD_SILO_REMOVE) {
+            silo_remove(remove_matches, &mut config)?
+        } else if let Some(set_default_matches) =
+            silo_matches.subcommand_matches(CMD_SILO_SET_DEFAULT)
+        {
+            silo_set_default(set_default_matches, &mut config)?
+        } else if silo_matches.subcommand_matches(CMD_SILO_SOURCES).is_some() {
+            silo_sources(&config)?
+        } else if let Some(cache_matches) = silo_matches.subcommand_matches(CMD_SILO_CACHE) {
+            if cache_matches
+                .subcommand_matches(CMD_SILO_CACHE_LIST)
+                .is_some()
+            {
+                cache_list()?
+            } else if cache_matches
+                .subcommand_matches(CMD_SILO_CACHE_CLEAR)
+                .is_some()
+            {
+                cache_clear()?
+            } else if cache_matches
+                .subcommand_matches(CMD_SILO_CACHE_REFRESH)
+                .is_some()
+            {
+                cache_refresh(cache_matches, &config)?
+            }
+        } else if let Some(build_matches) = silo_matches
+            .subcommand_matches(CMD_SILO_INDEX)
+            .and_then(|m| m.subcommand_matches(CMD_SILO_INDEX_BUILD))
+        {
+            index_build(build_matches)?
+        } else if let Some(build_matches) = silo_matches
+            .subcommand_matches(CMD_SILO_MANIFEST)
+            .and_then(|m| m.subcommand_matches(CMD_SILO_MANIFEST_BUILD))
+        {
+            manifest_build(build_matches)?
+        } else if let Some(vendor_matches) = silo_matches.subcommand_matches(CMD_SILO_VENDOR) {
+            silo_vendor(vendor_matches, &config)?
         }
     } else if let Some(plant_matches) = cmd_matches.subcommand_matches(CMD_PLANT) {
-        plant_seed(plant_matches)?
+        plant_seed(plant_matches, &mut config)?
+    } else if let Some(lint_matches) = cmd_matches
+        .subcommand_matches(CMD_SEED)
+        .and_then(|m| m.subcommand_matches(CMD_SEED_LINT))
+    {
+        seed_lint(lint_matches)?
     }
 
     Ok(())
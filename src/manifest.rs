@@ -0,0 +1,415 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Error, Result, anyhow};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use blake2::Blake2b512;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::{
+    constants::{SILO_MANIFEST_FILENAME, SILO_MANIFEST_SIGNATURE_FILENAME},
+    io::is_contained_path,
+};
+
+/// SHA-256 digests of all the files of a silo, stored at the silo root in the
+/// `sha256sum` format. The manifest is signed with minisign by the silo publisher.
+#[derive(Debug, Clone, Default)]
+pub struct SiloManifest {
+    /// Path of the file relative to the silo root -> hex encoded digest
+    pub digests: BTreeMap<String, String>,
+}
+
+impl SiloManifest {
+    /// Computes the digests of all the files of the silo
+    pub fn build(silo_path: impl AsRef<Path>) -> Result<SiloManifest, Error> {
+        let silo_path = silo_path.as_ref();
+        let mut digests = BTreeMap::new();
+
+        for path in silo_files(silo_path, silo_path) {
+            let relative_path = relative_path(silo_path, &path)?;
+            if relative_path == SILO_MANIFEST_FILENAME
+                || relative_path == SILO_MANIFEST_SIGNATURE_FILENAME
+            {
+                continue;
+            }
+
+            check_not_symlink(&path, &relative_path)?;
+            digests.insert(relative_path, sha256_file(&path)?);
+        }
+
+        Ok(SiloManifest { digests })
+    }
+
+    /// Reads the manifest of the silo and verifies its signature with the trusted keys.
+    /// Returns `None` if the silo doesn't have a manifest.
+    pub fn read(
+        silo_path: impl AsRef<Path>,
+        trusted_keys: &[String],
+    ) -> Result<Option<SiloManifest>, Error> {
+        let silo_path = silo_path.as_ref();
+        let path = silo_path.join(SILO_MANIFEST_FILENAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = fs::read(&path)
+            .with_context(|| format!("Failed to read silo manifest {}", path.display()))?;
+        let signature_path = silo_path.join(SILO_MANIFEST_SIGNATURE_FILENAME);
+        let signature = fs::read_to_string(&signature_path).with_context(|| {
+            format!(
+                "Silo manifest is not signed, {} is missing",
+                signature_path.display()
+            )
+        })?;
+        verify_signature(&content, &signature, trusted_keys)
+            .context("Failed to verify the signature of the silo manifest")?;
+
+        let mut digests = BTreeMap::new();
+        for line in String::from_utf8_lossy(&content).lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (digest, file_path) = line
+                .split_once("  ")
+                .with_context(|| format!("Invalid silo manifest line: {}", line))?;
+            digests.insert(file_path.to_string(), digest.to_lowercase());
+        }
+
+        Ok(Some(SiloManifest { digests }))
+    }
+
+    /// Writes the manifest to the silo root and returns its path
+    pub fn write(&self, silo_path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        let path = silo_path.as_ref().join(SILO_MANIFEST_FILENAME);
+        let content = self
+            .digests
+            .iter()
+            .map(|(file_path, digest)| format!("{}  {}\n", digest, file_path))
+            .collect::<String>();
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write silo manifest {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    /// Checks that the files inside the given directory of the silo are exactly the ones
+    /// listed in the manifest. Fails on the first file that is missing, changed or unknown.
+    pub fn verify_dir(&self, silo_path: impl AsRef<Path>, dir: &str) -> Result<(), Error> {
+        let silo_path = silo_path.as_ref();
+        let dir_path = manifest_path(Path::new(dir));
+        // Only the files inside the silo are listed, so nothing would be expected outside of it
+        if !is_contained_path(dir) || dir_path.is_empty() {
+            return Err(anyhow!(
+                "Seed directory \"{}\" cannot be verified, it must be a subdirectory of the silo",
+                dir
+            ));
+        }
+        let prefix = format!("{}/", dir_path);
+        let mut expected = self
+            .digests
+            .iter()
+            .filter(|(file_path, _)| file_path.starts_with(&prefix))
+            .collect::<BTreeMap<_, _>>();
+
+        for path in silo_files(silo_path, silo_path.join(dir)) {
+            let relative_path = relative_path(silo_path, &path)?;
+            check_not_symlink(&path, &relative_path)?;
+            let digest = expected
+                .remove(&relative_path)
+                .with_context(|| format!("{} is not listed in the silo manifest", relative_path))?;
+
+            if sha256_file(&path)? != *digest {
+                return Err(anyhow!(
+                    "{} doesn't match with its digest in the silo manifest",
+                    relative_path
+                ));
+            }
+        }
+
+        if let Some((file_path, _)) = expected.first_key_value() {
+            return Err(anyhow!(
+                "{} is listed in the silo manifest but missing",
+                file_path
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks a single file of the silo, e.g the silo index. A file that is not
+    /// listed in the manifest must not exist either.
+    pub fn verify_file(
+        &self,
+        silo_path: impl AsRef<Path>,
+        file_path: impl AsRef<Path>,
+    ) -> Result<(), Error> {
+        let path = silo_path.as_ref().join(&file_path);
+        let file_path = manifest_path(file_path.as_ref());
+
+        check_not_symlink(&path, &file_path)?;
+
+        match self.digests.get(&file_path) {
+            Some(_) if !path.is_file() => Err(anyhow!(
+                "{} is listed in the silo manifest but missing",
+                file_path
+            )),
+            Some(digest) if sha256_file(&path)? != *digest => Err(anyhow!(
+                "{} doesn't match with its digest in the silo manifest",
+                file_path
+            )),
+            None if path.exists() => {
+                Err(anyhow!("{} is not listed in the silo manifest", file_path))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Lists the files and the symlinks inside the given directory of the silo,
+/// skipping the `.git` directory
+fn silo_files(silo_path: &Path, dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(Result::ok)
+        .filter(|e| !e.file_type().is_dir() && e.path().starts_with(silo_path))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Symlinks cannot be signed since their digest would be the one of a file
+/// that may be outside of the silo, e.g `~/.ssh/id_rsa`
+fn check_not_symlink(path: &Path, relative_path: &str) -> Result<(), Error> {
+    if path.is_symlink() {
+        return Err(anyhow!(
+            "{} is a symlink, symlinks are not allowed in signed silos",
+            relative_path
+        ));
+    }
+
+    Ok(())
+}
+
+fn relative_path(silo_path: &Path, path: &Path) -> Result<String, Error> {
+    Ok(manifest_path(path.strip_prefix(silo_path)?))
+}
+
+/// Paths are listed in the manifest with `/` separators, e.g `node/cli-ts/.tohumrc`
+fn manifest_path(path: &Path) -> String {
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn sha256_file(path: impl AsRef<Path>) -> Result<String, Error> {
+    let path = path.as_ref();
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Verifies a minisign signature of the data with one of the trusted public keys
+fn verify_signature(data: &[u8], signature: &str, trusted_keys: &[String]) -> Result<(), Error> {
+    if trusted_keys.is_empty() {
+        return Err(anyhow!(
+            "There are no trusted keys. Add the public key of the silo publisher to \"trusted_keys\" in the config"
+        ));
+    }
+
+    let lines = signature
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let [
+        _,
+        signature_line,
+        trusted_comment_line,
+        global_signature_line,
+    ] = lines[..]
+    else {
+        return Err(anyhow!("Invalid minisign signature"));
+    };
+
+    let signature = decode_base64(signature_line)?;
+    if signature.len() != 74 {
+        return Err(anyhow!("Invalid minisign signature"));
+    }
+    let (algorithm, key_id, signature) = (&signature[..2], &signature[2..10], &signature[10..]);
+
+    let public_key = trusted_keys
+        .iter()
+        .map(|key| parse_public_key(key))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .find(|(id, _)| id == key_id)
+        .map(|(_, public_key)| public_key)
+        .with_context(|| {
+            format!(
+                "Signed with key {} which is not trusted",
+                hex::encode_upper(key_id.iter().rev().copied().collect::<Vec<_>>())
+            )
+        })?;
+
+    // Signatures of the "ED" algorithm are made over the BLAKE2b hash of the data
+    let message = match algorithm {
+        b"Ed" => data.to_vec(),
+        b"ED" => Blake2b512::digest(data).to_vec(),
+        _ => return Err(anyhow!("Unsupported minisign signature algorithm")),
+    };
+    let signature = Signature::from_slice(signature)?;
+    public_key
+        .verify(&message, &signature)
+        .map_err(|_| anyhow!("Signature doesn't match"))?;
+
+    // The trusted comment is signed along with the signature
+    let trusted_comment = trusted_comment_line
+        .strip_prefix("trusted comment: ")
+        .context("Invalid minisign signature")?;
+    let global_signature = Signature::from_slice(&decode_base64(global_signature_line)?)?;
+    public_key
+        .verify(
+            &[signature.to_bytes().as_slice(), trusted_comment.as_bytes()].concat(),
+            &global_signature,
+        )
+        .map_err(|_| anyhow!("Trusted comment of the signature doesn't match"))?;
+
+    Ok(())
+}
+
+/// Parses a minisign public key. The content of a `.pub` file is accepted as well.
+fn parse_public_key(key: &str) -> Result<([u8; 8], VerifyingKey), Error> {
+    let line = key
+        .lines()
+        .rfind(|line| !line.trim().is_empty())
+        .unwrap_or_default();
+    let bytes = decode_base64(line)?;
+
+    if bytes.len() != 42 || &bytes[..2] != b"Ed" {
+        return Err(anyhow!("Invalid minisign public key {}", line.trim()));
+    }
+
+    let key_id = bytes[2..10].try_into()?;
+    let public_key = VerifyingKey::from_bytes(bytes[10..].try_into()?)
+        .with_context(|| format!("Invalid minisign public key {}", line.trim()))?;
+
+    Ok((key_id, public_key))
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, Error> {
+    BASE64_STANDARD
+        .decode(value.trim())
+        .context("Invalid base64 value")
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn silo() -> (TempDir, SiloManifest) {
+        let silo = TempDir::new().unwrap();
+        fs::create_dir_all(silo.path().join("node/cli")).unwrap();
+        fs::write(silo.path().join("node/cli/.tohumrc"), "{}").unwrap();
+        fs::write(silo.path().join("node/cli/main.ts"), "main").unwrap();
+        fs::write(silo.path().join("tohum-index.json"), "{}").unwrap();
+        let manifest = SiloManifest::build(silo.path()).unwrap();
+
+        (silo, manifest)
+    }
+
+    #[test]
+    fn verifies_seed_dir() {
+        let (silo, manifest) = silo();
+        manifest.verify_dir(silo.path(), "node/cli").unwrap();
+        manifest.verify_dir(silo.path(), "./node/cli/").unwrap();
+
+        fs::write(silo.path().join("node/cli/main.ts"), "changed").unwrap();
+        let err = manifest.verify_dir(silo.path(), "node/cli").unwrap_err();
+        assert!(err.to_string().contains("doesn't match"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_seed_dir_outside_of_silo() {
+        let (silo, manifest) = silo();
+
+        for dir in ["", ".", "/tmp", "..", "node/../.."] {
+            let err = manifest.verify_dir(silo.path(), dir).unwrap_err();
+            assert!(err.to_string().contains("subdirectory"), "{}: {}", dir, err);
+        }
+    }
+
+    #[test]
+    fn verifies_single_file() {
+        let (silo, manifest) = silo();
+        manifest
+            .verify_file(silo.path(), "tohum-index.json")
+            .unwrap();
+        // A file that is neither listed nor exists is fine
+        manifest
+            .verify_file(silo.path(), "tohum-silo.json")
+            .unwrap();
+
+        fs::write(silo.path().join("tohum-index.json"), "[]").unwrap();
+        let err = manifest
+            .verify_file(silo.path(), "tohum-index.json")
+            .unwrap_err();
+        assert!(err.to_string().contains("doesn't match"), "{}", err);
+
+        fs::write(silo.path().join("tohum-silo.json"), "{}").unwrap();
+        let err = manifest
+            .verify_file(silo.path(), "tohum-silo.json")
+            .unwrap_err();
+        assert!(err.to_string().contains("not listed"), "{}", err);
+
+        fs::remove_file(silo.path().join("tohum-index.json")).unwrap();
+        let err = manifest
+            .verify_file(silo.path(), "tohum-index.json")
+            .unwrap_err();
+        assert!(err.to_string().contains("missing"), "{}", err);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks() {
+        let (silo, manifest) = silo();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("id_rsa"), "secret").unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("id_rsa"),
+            silo.path().join("node/cli/link"),
+        )
+        .unwrap();
+
+        let err = manifest.verify_dir(silo.path(), "node/cli").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "node/cli/link is a symlink, symlinks are not allowed in signed silos"
+        );
+        let err = SiloManifest::build(silo.path()).unwrap_err();
+        assert!(err.to_string().contains("node/cli/link"), "{}", err);
+
+        fs::remove_file(silo.path().join("tohum-index.json")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("id_rsa"),
+            silo.path().join("tohum-index.json"),
+        )
+        .unwrap();
+        let err = manifest
+            .verify_file(silo.path(), "tohum-index.json")
+            .unwrap_err();
+        assert!(err.to_string().contains("is a symlink"), "{}", err);
+    }
+}
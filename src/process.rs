@@ -2,11 +2,23 @@ use std::process::Output;
 
 use anyhow::Error;
 
-pub fn check_exit_status(output: Output) -> Result<(), Error> {
+/// Returns the stderr of a failed command as an error. The given
+/// secrets (e.g access tokens) are redacted from the error message.
+pub fn check_exit_status(output: Output, secrets: &[String]) -> Result<(), Error> {
     if !output.status.success() {
-        let err_output = String::from_utf8_lossy(&output.stderr).into_owned();
-        return Err(Error::msg(err_output));
+        let err_output = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::msg(redact(&err_output, secrets)));
     }
 
     Ok(())
 }
+
+/// Replaces all the occurrences of the secrets in the given text
+pub fn redact(text: &str, secrets: &[String]) -> String {
+    let mut text = text.to_string();
+    for secret in secrets.iter().filter(|s| !s.is_empty()) {
+        text = text.replace(secret.as_str(), "********");
+    }
+
+    text
+}
@@ -1,7 +1,15 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
 
-use semver::Version;
+use anyhow::{Context, Error, Result, anyhow};
+use regex::Regex;
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::template::{context_variables, new_template};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SeedAuthor {
@@ -10,20 +18,280 @@ pub struct SeedAuthor {
     pub website: Option<String>,
 }
 
+/// Type of a template variable. The values given via `--var` flag are converted
+/// to this type, so they can be used e.g in comparisons inside the templates.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    /// Given as comma separated values or a JSON array
+    List,
+}
+
+impl VariableType {
+    /// Converts a value given via the command line to this type
+    pub fn parse_value(&self, value: &str) -> Result<Value, Error> {
+        let invalid = || anyhow!("\"{}\" is not a valid {}", value, self);
+        let trimmed = value.trim();
+
+        match self {
+            VariableType::String => Ok(Value::String(value.to_string())),
+            VariableType::Number => {
+                let number = match trimmed.parse::<i64>() {
+                    Ok(integer) => integer.into(),
+                    Err(_) => trimmed
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .ok_or_else(invalid)?,
+                };
+                Ok(Value::Number(number))
+            }
+            VariableType::Integer => trimmed
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| invalid()),
+            VariableType::Boolean => match trimmed.to_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok(Value::Bool(true)),
+                "false" | "no" | "0" => Ok(Value::Bool(false)),
+                _ => Err(invalid()),
+            },
+            VariableType::List if trimmed.starts_with('[') => {
+                match serde_json::from_str::<Value>(trimmed) {
+                    Ok(list @ Value::Array(_)) => Ok(list),
+                    _ => Err(invalid()),
+                }
+            }
+            VariableType::List => Ok(Value::Array(
+                trimmed
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| Value::String(item.to_string()))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Checks whether the value (e.g a default value) is of this type
+    pub fn matches(&self, value: &Value) -> bool {
+        match self {
+            VariableType::String => value.is_string(),
+            VariableType::Number => value.is_number(),
+            VariableType::Integer => value.is_i64() || value.is_u64(),
+            VariableType::Boolean => value.is_boolean(),
+            VariableType::List => value.is_array(),
+        }
+    }
+}
+
+impl fmt::Display for VariableType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VariableType::String => "string",
+            VariableType::Number => "number",
+            VariableType::Integer => "integer",
+            VariableType::Boolean => "boolean",
+            VariableType::List => "list",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SeedTemplateVariable {
     #[serde(rename = "type")]
-    pub var_type: String,
+    pub var_type: VariableType,
     pub default: Option<serde_json::Value>,
+    /// The default value is a Tera template that refers to the other variables
+    #[serde(default)]
+    pub computed: bool,
     pub description: String,
     #[serde(default)]
     pub required: bool,
+    /// Values that the variable is allowed to have. Each item of a list must be one of them.
+    pub choices: Option<Vec<Value>>,
+    /// Regular expression that the strings (or each item of a list) must match
+    pub pattern: Option<String>,
+    /// Minimum number of characters of a string or items of a list
+    pub min_length: Option<usize>,
+    /// Maximum number of characters of a string or items of a list
+    pub max_length: Option<usize>,
+    /// Minimum value of a number
+    pub minimum: Option<f64>,
+    /// Maximum value of a number
+    pub maximum: Option<f64>,
+    /// Tera expression over the other variables, e.g `enable_grpc`. The variable is
+    /// only required, asked and validated when the expression holds.
+    pub when: Option<String>,
+}
+
+impl SeedTemplateVariable {
+    /// Returns the default value if it is marked as a template that refers to other variables
+    pub fn computed_default(&self) -> Option<&str> {
+        self.default.as_ref()?.as_str().filter(|_| self.computed)
+    }
+
+    /// Template that renders to `true` when the condition of the variable holds
+    pub fn condition_template(&self) -> Option<String> {
+        self.when
+            .as_ref()
+            .map(|when| format!("{{% if {} %}}true{{% endif %}}", when))
+    }
+
+    /// Variables that the condition of the variable refers to
+    pub fn condition_variables(&self, name: &str) -> Result<BTreeSet<String>, Error> {
+        let Some(condition) = self.condition_template() else {
+            return Ok(BTreeSet::new());
+        };
+        let tera = new_template(name, &condition)
+            .with_context(|| format!("Condition of variable {} cannot be parsed", name))?;
+
+        context_variables(&tera, name)
+    }
+
+    /// Variables that the computed default value of the variable refers to
+    pub fn default_variables(&self, name: &str) -> Result<BTreeSet<String>, Error> {
+        let Some(default) = self.computed_default() else {
+            return Ok(BTreeSet::new());
+        };
+        let tera = new_template(name, default)
+            .with_context(|| format!("Default value of variable {} cannot be parsed", name))?;
+
+        context_variables(&tera, name)
+    }
+
+    /// Converts a value given via the command line and checks that it is allowed
+    pub fn parse_value(&self, value: &str) -> Result<Value, Error> {
+        let value = self.var_type.parse_value(value)?;
+        self.validate(&value)?;
+
+        Ok(value)
+    }
+
+    /// Checks the value against the constraints of the variable. All the violated
+    /// constraints are reported at once.
+    pub fn validate(&self, value: &Value) -> Result<(), Error> {
+        let violations = self.violations(value);
+        if !violations.is_empty() {
+            return Err(anyhow!(violations.join("; ")));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the constraints of the variable that the value violates
+    pub fn violations(&self, value: &Value) -> Vec<String> {
+        let mut violations = vec![];
+        let items = match value {
+            Value::Array(items) => items.iter().collect(),
+            _ => vec![value],
+        };
+
+        if let Some(choices) = &self.choices {
+            let is_choice = |item: &Value| choices.iter().any(|choice| is_same_value(choice, item));
+            for item in items.iter().filter(|item| !is_choice(item)) {
+                violations.push(format!(
+                    "{} is not one of {}",
+                    display_value(item),
+                    choices
+                        .iter()
+                        .map(display_value)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+
+        let strings = items
+            .iter()
+            .filter_map(|item| item.as_str())
+            .collect::<Vec<_>>();
+        if let Some(pattern) = &self.pattern
+            && !strings.is_empty()
+        {
+            match compile_pattern(pattern) {
+                Ok(regex) => {
+                    for item in strings.iter().filter(|item| !regex.is_match(item)) {
+                        violations.push(format!("{} doesn't match {}", item, pattern));
+                    }
+                }
+                Err(err) => violations.push(err.to_string()),
+            }
+        }
+
+        let (length, unit) = match value {
+            Value::String(s) => (Some(s.chars().count()), "characters"),
+            Value::Array(items) => (Some(items.len()), "items"),
+            _ => (None, ""),
+        };
+        if let (Some(length), Some(min_length)) = (length, self.min_length)
+            && length < min_length
+        {
+            violations.push(format!("must have at least {} {}", min_length, unit));
+        }
+        if let (Some(length), Some(max_length)) = (length, self.max_length)
+            && length > max_length
+        {
+            violations.push(format!("must have at most {} {}", max_length, unit));
+        }
+
+        if let Some(number) = value.as_f64() {
+            if let Some(minimum) = self.minimum
+                && number < minimum
+            {
+                violations.push(format!("must be at least {}", minimum));
+            }
+            if let Some(maximum) = self.maximum
+                && number > maximum
+            {
+                violations.push(format!("must be at most {}", maximum));
+            }
+        }
+
+        violations
+    }
+}
+
+/// Compiles the pattern of a variable. The error of regex spans multiple lines
+/// to point the problem, only its last line is kept.
+pub fn compile_pattern(pattern: &str) -> Result<Regex, Error> {
+    Regex::new(pattern).map_err(|err| {
+        let message = err.to_string();
+        let reason = message.lines().last().unwrap_or_default();
+        anyhow!(
+            "invalid pattern {}: {}",
+            pattern,
+            reason.trim_start_matches("error: ")
+        )
+    })
+}
+
+/// Numbers are compared by their values, so `2.0` given for a number is the same as `2`
+fn is_same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => a == b,
+    }
+}
+
+/// Strings are shown without quotes, the rest as JSON
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Seed {
     pub name: String,
     pub version: Version,
+    /// Versions of tohum that can plant the seed
+    pub tohum: Option<VersionReq>,
     pub description: Option<String>,
     pub tags: Option<Vec<String>>,
     pub authors: Vec<SeedAuthor>,
@@ -31,3 +299,241 @@ pub struct Seed {
     #[serde(skip)]
     pub repo_path: String,
 }
+
+impl Seed {
+    /// Checks what deserializing the seed doesn't. Returns the problem of an invalid seed.
+    pub fn problem(&self) -> Option<&'static str> {
+        self.authors
+            .is_empty()
+            .then_some("Seed must include at least one author")
+    }
+
+    /// Checks whether the running version of tohum satisfies the requirement of the seed
+    pub fn is_compatible(&self) -> bool {
+        is_tohum_compatible(self.tohum.as_ref())
+    }
+
+    /// Variables that each variable of the seed depends on: the ones that
+    /// its computed default value and its condition refer to
+    pub fn variable_dependencies(&self) -> Result<BTreeMap<String, BTreeSet<String>>, Error> {
+        let mut dependencies = BTreeMap::new();
+
+        for (name, variable) in self.variables.iter().flatten() {
+            let mut variables = variable.default_variables(name)?;
+            variables.extend(variable.condition_variables(name)?);
+            dependencies.insert(name.clone(), variables);
+        }
+
+        Ok(dependencies)
+    }
+
+    /// Declared variable that the condition of the given variable refers to. The
+    /// variable is shown under it. Variables without a condition have no parent.
+    pub fn parent_variable(&self, name: &str) -> Option<&str> {
+        let variables = self.variables.as_ref()?;
+        let condition_variables = variables.get(name)?.condition_variables(name).ok()?;
+
+        condition_variables
+            .into_iter()
+            .find(|parent| parent != name && variables.contains_key(parent))
+            .and_then(|parent| variables.get_key_value(&parent))
+            .map(|(parent, _)| parent.as_str())
+    }
+}
+
+/// Checks whether the running version of tohum satisfies the given requirement
+pub fn is_tohum_compatible(requirement: Option<&VersionReq>) -> bool {
+    let current = Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
+    requirement.is_none_or(|req| req.matches(&current))
+}
+
+/// Splits a seed name and its optional semver requirement (e.g `@node/cli-ts@^1.2`)
+pub fn parse_seed_spec(spec: &str) -> Result<(&str, Option<VersionReq>), Error> {
+    // Seed names start with "@" so the requirement is after the last one
+    match spec.rfind('@') {
+        Some(index) if index > 0 => {
+            let (name, requirement) = (&spec[..index], &spec[index + 1..]);
+            let requirement = VersionReq::parse(requirement)
+                .with_context(|| format!("Invalid version requirement {}", requirement))?;
+
+            Ok((name, Some(requirement)))
+        }
+        _ => Ok((spec, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn variable(definition: Value) -> SeedTemplateVariable {
+        serde_json::from_value(definition).unwrap()
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(
+            VariableType::Number.parse_value("8080").unwrap(),
+            json!(8080)
+        );
+        assert_eq!(
+            VariableType::Number.parse_value(" 1.5 ").unwrap(),
+            json!(1.5)
+        );
+        assert_eq!(VariableType::Number.parse_value("-2").unwrap(), json!(-2));
+        assert!(VariableType::Number.parse_value("NaN").is_err());
+        assert!(VariableType::Number.parse_value("ten").is_err());
+
+        assert_eq!(VariableType::Integer.parse_value("42").unwrap(), json!(42));
+        let err = VariableType::Integer.parse_value("4.2").unwrap_err();
+        assert_eq!(err.to_string(), "\"4.2\" is not a valid integer");
+    }
+
+    #[test]
+    fn parses_booleans() {
+        for value in ["true", "Yes", "1"] {
+            assert_eq!(
+                VariableType::Boolean.parse_value(value).unwrap(),
+                json!(true)
+            );
+        }
+        for value in ["false", "NO", "0"] {
+            assert_eq!(
+                VariableType::Boolean.parse_value(value).unwrap(),
+                json!(false)
+            );
+        }
+        assert!(VariableType::Boolean.parse_value("maybe").is_err());
+    }
+
+    #[test]
+    fn parses_lists() {
+        assert_eq!(
+            VariableType::List.parse_value("a, b,,c ").unwrap(),
+            json!(["a", "b", "c"])
+        );
+        assert_eq!(VariableType::List.parse_value("").unwrap(), json!([]));
+        assert_eq!(
+            VariableType::List.parse_value(r#"["a,b", "c"]"#).unwrap(),
+            json!(["a,b", "c"])
+        );
+        assert!(VariableType::List.parse_value("[not json").is_err());
+    }
+
+    #[test]
+    fn strings_are_kept_as_is() {
+        assert_eq!(
+            VariableType::String.parse_value(" 42 ").unwrap(),
+            json!(" 42 ")
+        );
+    }
+
+    #[test]
+    fn matches_number_choices_by_value() {
+        let variable = variable(json!({
+            "type": "number",
+            "description": "Replicas",
+            "choices": [1, 2, 4.5],
+        }));
+
+        let value = variable.parse_value("2.0").unwrap();
+        assert_eq!(value, json!(2.0));
+        assert!(variable.validate(&value).is_ok());
+        assert!(variable.parse_value("4.50").is_ok());
+        assert_eq!(
+            variable.parse_value("3").unwrap_err().to_string(),
+            "3 is not one of 1, 2, 4.5"
+        );
+    }
+
+    #[test]
+    fn reports_all_violations() {
+        let variable = variable(json!({
+            "type": "list",
+            "description": "Services",
+            "choices": ["api", "web", "worker"],
+            "pattern": "^[a-z]+$",
+            "max_length": 2,
+        }));
+
+        assert!(variable.parse_value("api,web").is_ok());
+        assert_eq!(
+            variable.violations(&json!(["api", "Db", "web"])),
+            [
+                "Db is not one of api, web, worker",
+                "Db doesn't match ^[a-z]+$",
+                "must have at most 2 items",
+            ]
+        );
+        assert_eq!(
+            variable.parse_value("Db,web,api").unwrap_err().to_string(),
+            "Db is not one of api, web, worker; Db doesn't match ^[a-z]+$; must have at most 2 items"
+        );
+    }
+
+    #[test]
+    fn checks_string_and_number_constraints() {
+        let name = variable(json!({
+            "type": "string",
+            "description": "Name",
+            "pattern": "^[a-z-]+$",
+            "min_length": 3,
+            "max_length": 5,
+        }));
+        assert!(
+            name.violations(&json!("my-app"))
+                .contains(&"must have at most 5 characters".to_string())
+        );
+        assert_eq!(
+            name.violations(&json!("A")),
+            [
+                "A doesn't match ^[a-z-]+$",
+                "must have at least 3 characters"
+            ]
+        );
+        // Lengths are counted in characters, not bytes
+        assert!(
+            name.violations(&json!("çöğ"))
+                .iter()
+                .all(|v| !v.contains("characters"))
+        );
+
+        let port = variable(json!({
+            "type": "integer",
+            "description": "Port",
+            "minimum": 1024,
+            "maximum": 65535,
+        }));
+        assert!(port.violations(&json!(8080)).is_empty());
+        assert_eq!(port.violations(&json!(80)), ["must be at least 1024"]);
+        assert_eq!(port.violations(&json!(70000)), ["must be at most 65535"]);
+    }
+
+    #[test]
+    fn computed_default_is_opt_in() {
+        let literal = variable(json!({
+            "type": "string",
+            "description": "Token",
+            "default": "${{ secrets.TOKEN }}",
+        }));
+        assert_eq!(literal.computed_default(), None);
+        assert!(literal.default_variables("token").unwrap().is_empty());
+
+        let computed = variable(json!({
+            "type": "string",
+            "description": "Crate",
+            "default": "{{ project_name | snake_case }}",
+            "computed": true,
+        }));
+        assert_eq!(
+            computed.computed_default(),
+            Some("{{ project_name | snake_case }}")
+        );
+        assert_eq!(
+            computed.default_variables("crate_name").unwrap(),
+            BTreeSet::from(["project_name".to_string()])
+        );
+    }
+}
@@ -1,34 +1,672 @@
-use anyhow::{Context, Error, Result};
+use anyhow::{Context, Error, Result, anyhow};
 use colored::Colorize;
-use std::{ffi::OsStr, fs::File, io::BufReader, path::Path};
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 use tempfile::TempDir;
 use walkdir::WalkDir;
 
 use crate::{
-    constants::{TOHUMRC_FILENAME, TOHUMRC_GLOB_PATTERN},
-    git::git_sparse_clone,
-    seed::Seed,
+    archive::{ArchiveFormat, is_remote, unpack_archive},
+    cache::{CacheEntry, cache_entry, remove_cache_entry},
+    config::{Config, ResolvedSilo, rewrite_url},
+    constants::{
+        SILO_INDEX_FILENAME, SILO_INDEX_PATTERN, SILO_MANIFEST_FILENAME, SILO_MANIFEST_PATTERNS,
+        SILO_METADATA_FILENAME, SILO_METADATA_PATTERN, TOHUMRC_FILENAME, TOHUMRC_GLOB_PATTERN,
+    },
+    diagnostic::Diagnostic,
+    git::{GitBackend, GitBackendKind, is_commit_sha},
+    index::SiloIndex,
+    manifest::SiloManifest,
+    seed::{Seed, SeedAuthor, is_tohum_compatible},
+    vendor::VendorManifest,
 };
 
-pub type Silo = Vec<Seed>;
+/// Content of a silo: the optional metadata file at its root and the seeds
+#[derive(Debug, Clone)]
+pub struct Silo {
+    pub metadata: Option<SiloMetadata>,
+    pub seeds: Vec<Seed>,
+    /// Problems of the `.tohumrc` files that are skipped while reading the seeds
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Describes the silo itself, read from the metadata file at the silo root
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiloMetadata {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub maintainers: Vec<SeedAuthor>,
+    pub homepage: Option<String>,
+    /// Branch that the silo is maintained on
+    pub default_branch: Option<String>,
+    /// Versions of tohum that can plant the seeds of the silo
+    pub tohum: Option<VersionReq>,
+}
+
+impl SiloMetadata {
+    /// Checks whether the running version of tohum satisfies the requirement of the silo
+    pub fn is_compatible(&self) -> bool {
+        is_tohum_compatible(self.tohum.as_ref())
+    }
+}
+
+enum SiloSource {
+    /// A silo inside a directory of the local file system. It doesn't need to be a git repository.
+    Local(PathBuf),
+    /// A git silo that is cloned into the cache
+    Git(CacheEntry),
+    /// An archive file that is unpacked into a temporary directory
+    Archive(TempDir),
+}
+
+/// Options of how the silos are fetched
+#[derive(Debug, Clone)]
+pub struct SiloOptions {
+    /// Never touch the network, only use the cached or local silos
+    pub offline: bool,
+    pub git_backend: GitBackendKind,
+    /// Public keys that the silo manifests are verified with
+    pub trusted_keys: Vec<String>,
+    pub require_signed_silos: bool,
+    pub url_rewrites: BTreeMap<String, String>,
+    /// Fail instead of skipping the invalid seeds
+    pub strict: bool,
+}
+
+impl SiloOptions {
+    /// `offline` is the value of the `--offline` flag, offline mode can be enabled from the config as well.
+    /// `strict` is the value of the `--strict` flag.
+    pub fn new(config: &Config, offline: bool, strict: bool) -> SiloOptions {
+        SiloOptions {
+            offline: config.offline || offline,
+            strict,
+            git_backend: config.git_backend,
+            trusted_keys: config.trusted_keys.clone(),
+            require_signed_silos: config.require_signed_silos,
+            url_rewrites: config.url_rewrites.clone(),
+        }
+    }
+
+    /// URLs that the silo is fetched from in order: the silo URL and then its
+    /// mirrors, all of them with the rewrite rules applied
+    pub fn fetch_urls(&self, silo: &ResolvedSilo) -> Vec<String> {
+        let mut urls: Vec<String> = vec![];
+        for url in std::iter::once(&silo.url).chain(&silo.mirrors) {
+            let url = rewrite_url(url, &self.url_rewrites);
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+
+        urls
+    }
+}
+
+/// An opened silo. The silo is fetched only once, when the handle is opened,
+/// and then the seeds are checked out on demand by widening the sparse-checkout.
+pub struct SiloHandle {
+    silo: ResolvedSilo,
+    options: SiloOptions,
+    source: SiloSource,
+    /// Verified manifest of the silo, if the silo is signed and there are trusted keys
+    manifest: Option<SiloManifest>,
+    /// Where the silo comes from, if it is a snapshot made by `silo vendor`
+    vendor: Option<VendorManifest>,
+    /// Whether the user is already warned about the invalid seeds of the silo
+    invalid_seeds_warned: Cell<bool>,
+}
+
+impl SiloHandle {
+    /// Opens the silo at the given URL or local path. Git silos are read from their
+    /// cached clone. The cache is updated first and if that is not possible (e.g no
+    /// network connection), the cached copy is used. In offline mode the cache is used
+    /// as is, without trying to update it.
+    pub fn open(silo: &ResolvedSilo, options: &SiloOptions) -> Result<SiloHandle, Error> {
+        let url = silo.url.as_str();
+        let local_path = PathBuf::from(url);
+
+        let source = if local_path.is_dir() && !is_bare_repository(&local_path) {
+            SiloSource::Local(local_path)
+        } else if let Some(format) = ArchiveFormat::detect(url) {
+            SiloSource::Archive(open_archive_silo(url, format, options.offline)?)
+        } else {
+            SiloSource::Git(open_cached_silo(silo, options)?)
+        };
+
+        let mut handle = SiloHandle {
+            silo: silo.clone(),
+            options: options.clone(),
+            source,
+            manifest: None,
+            vendor: None,
+            invalid_seeds_warned: Cell::new(false),
+        };
+        handle.manifest = handle.verify_manifest()?;
+        if !matches!(handle.source, SiloSource::Git(_)) {
+            handle.vendor = VendorManifest::read(handle.path())?;
+        }
+
+        Ok(handle)
+    }
+
+    /// Reads the manifest of the silo and verifies its signature. The signature
+    /// can only be verified if there are trusted keys in the config.
+    fn verify_manifest(&self) -> Result<Option<SiloManifest>, Error> {
+        let path = self.path();
+        let is_signed = path.join(SILO_MANIFEST_FILENAME).is_file();
+
+        if self.options.require_signed_silos && !is_signed {
+            return Err(anyhow!(
+                "Silo {} is not signed. Only the signed silos are allowed by the config",
+                self.display_name().cyan()
+            ));
+        }
+
+        if is_signed && self.options.trusted_keys.is_empty() {
+            eprintln!(
+                "{}: Silo {} is signed but there are no trusted keys in the config, its signature is not verified",
+                "Warning".yellow(),
+                self.display_name().cyan()
+            );
+            return Ok(None);
+        }
+
+        let manifest = SiloManifest::read(&path, &self.options.trusted_keys)
+            .with_context(|| format!("Silo {} cannot be trusted", self.display_name().cyan()))?;
+
+        // The seeds and their requirements are read from these files before any seed is checked out
+        if let Some(manifest) = &manifest {
+            for file_path in [SILO_INDEX_FILENAME, SILO_METADATA_FILENAME] {
+                manifest.verify_file(&path, file_path).with_context(|| {
+                    format!(
+                        "Silo {} doesn't match with its signed manifest",
+                        self.display_name().cyan()
+                    )
+                })?;
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn resolved(&self) -> &ResolvedSilo {
+        &self.silo
+    }
+
+    pub fn url(&self) -> &str {
+        &self.silo.url
+    }
+
+    pub fn reference(&self) -> &str {
+        &self.silo.reference
+    }
+
+    /// Name of the silo if it is a registered one, otherwise its URL
+    pub fn display_name(&self) -> &str {
+        self.silo.display_name()
+    }
+
+    /// SHA of the commit that the silo reference was resolved to. Local silos are
+    /// read as is so they only have one if they are snapshots of a git silo.
+    pub fn commit(&self) -> Option<&str> {
+        match &self.source {
+            SiloSource::Local(_) | SiloSource::Archive(_) => {
+                self.vendor.as_ref()?.commit.as_deref()
+            }
+            SiloSource::Git(entry) => Some(&entry.commit),
+        }
+    }
+
+    /// Origin of the silo if it is a snapshot made by `silo vendor`
+    pub fn vendor(&self) -> Option<&VendorManifest> {
+        self.vendor.as_ref()
+    }
+
+    /// Root directory of the silo's working tree
+    pub fn path(&self) -> PathBuf {
+        match &self.source {
+            SiloSource::Local(path) => path.clone(),
+            SiloSource::Git(entry) => entry.repo_path(),
+            SiloSource::Archive(temp_dir) => temp_dir.path().to_path_buf(),
+        }
+    }
+
+    /// Reads the content of the silo. In strict mode, invalid seeds are an error.
+    pub fn read(&self) -> Result<Silo, Error> {
+        let path = self.path();
+        // A committed index would hide the working tree changes of a local silo
+        let use_index = !matches!(self.source, SiloSource::Local(_));
+        let silo = read_silo(&path, use_index)?;
+
+        // Seeds of a silo without an index are read from their `.tohumrc` files
+        if let Some(manifest) = &self.manifest {
+            for seed in &silo.seeds {
+                let seed_file = Path::new(&seed.repo_path).join(TOHUMRC_FILENAME);
+                if path.join(&seed_file).is_file() {
+                    manifest.verify_file(&path, &seed_file).with_context(|| {
+                        format!(
+                            "Silo {} doesn't match with its signed manifest",
+                            self.display_name().cyan()
+                        )
+                    })?;
+                }
+            }
+        }
+
+        if self.options.strict && !silo.diagnostics.is_empty() {
+            return Err(anyhow!(
+                "Silo {} has {} invalid seed(s):\n{}",
+                self.display_name().cyan(),
+                silo.diagnostics.len(),
+                format_diagnostics(&silo.diagnostics)
+            ));
+        }
+
+        Ok(silo)
+    }
+
+    /// Reads the seeds of the silo. The invalid seeds are skipped with a warning.
+    pub fn seeds(&self) -> Result<Vec<Seed>, Error> {
+        let silo = self.read()?;
+
+        if !silo.diagnostics.is_empty() && !self.invalid_seeds_warned.replace(true) {
+            eprintln!(
+                "{}: Skipped {} invalid seed(s) of silo {}. Run \"tohum silo list\" to see the details",
+                "Warning".yellow(),
+                silo.diagnostics.len(),
+                self.display_name().cyan()
+            );
+        }
+
+        Ok(silo.seeds)
+    }
+
+    /// Makes all the files of the given seed available and returns the seed directory
+    pub fn checkout_seed(&self, seed: &Seed) -> Result<PathBuf, Error> {
+        let seed_path = self.checkout_seed_files(seed)?;
+
+        // Nothing is copied from the silo before the files are verified
+        if let Some(manifest) = &self.manifest {
+            manifest
+                .verify_dir(self.path(), &seed.repo_path)
+                .with_context(|| {
+                    format!(
+                        "Seed {} doesn't match with the signed manifest of silo {}",
+                        seed.name.cyan(),
+                        self.display_name().cyan()
+                    )
+                })?;
+        }
+
+        Ok(seed_path)
+    }
+
+    fn checkout_seed_files(&self, seed: &Seed) -> Result<PathBuf, Error> {
+        let glob_pattern = PathBuf::from(&seed.repo_path).join("*");
+
+        match &self.source {
+            // Seeds are read directly from the disk, so the uncommitted changes of local silos are included
+            SiloSource::Local(_) | SiloSource::Archive(_) => Ok(self.path().join(&seed.repo_path)),
+            SiloSource::Git(entry) => {
+                // The seed stays checked out inside the cached silo so it is
+                // available for the offline usage. In offline mode the objects
+                // must be already there.
+                let offline = self.options.offline;
+                // Missing objects are fetched from the URL that the silo was last fetched from
+                let token = entry
+                    .fetched_url
+                    .as_deref()
+                    .and_then(|url| self.silo.token_for(url));
+                let result = entry.backend.backend(token).sparse_add(
+                    &entry.repo_path(),
+                    &glob_pattern,
+                    offline,
+                );
+
+                result.with_context(
+                    || {
+                        if offline {
+                            format!(
+                                "Seed {} of silo {} is not available offline. Plant it once without \"--offline\" to cache it",
+                                seed.name.cyan(),
+                                self.url().cyan()
+                            )
+                        } else {
+                            format!("Failed to check out seed {}", seed.name.cyan())
+                        }
+                    },
+                )?;
+
+                Ok(entry.repo_path().join(&seed.repo_path))
+            }
+        }
+    }
+}
+
+/// Opens the given silos in order. If there are multiple silos, the ones
+/// that cannot be opened are skipped with a warning.
+pub fn open_silos(silos: &[ResolvedSilo], options: &SiloOptions) -> Result<Vec<SiloHandle>, Error> {
+    if let [silo] = silos {
+        return Ok(vec![SiloHandle::open(silo, options)?]);
+    }
+
+    let mut handles = vec![];
+    for silo in silos {
+        match SiloHandle::open(silo, options) {
+            Ok(handle) => handles.push(handle),
+            Err(err) => warn_silo_skipped(silo, &err),
+        }
+    }
+
+    if handles.is_empty() {
+        return Err(anyhow!("None of the silos could be opened"));
+    }
+
+    Ok(handles)
+}
+
+/// Looks for the seed in the given silos. The silos are opened in order and the
+/// first one that has a matching version of the seed is returned along with the
+/// latest matching version. Without a version requirement, the latest version is used.
+pub fn find_seed(
+    silos: &[ResolvedSilo],
+    seed_name: &str,
+    version_req: Option<&VersionReq>,
+    options: &SiloOptions,
+) -> Result<(SiloHandle, Seed), Error> {
+    let mut available_versions = vec![];
+
+    for silo in silos {
+        let handle = match SiloHandle::open(silo, options) {
+            Ok(handle) => handle,
+            Err(err) if silos.len() > 1 => {
+                warn_silo_skipped(silo, &err);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let versions = seed_versions(handle.seeds()?, seed_name);
+        let seed = versions
+            .iter()
+            .find(|s| version_req.is_none_or(|req| req.matches(&s.version)))
+            .cloned();
+
+        if let Some(seed) = seed {
+            return Ok((handle, seed));
+        }
+        available_versions.extend(versions.into_iter().map(|s| s.version.to_string()));
+    }
+
+    if let Some(version_req) = version_req
+        && !available_versions.is_empty()
+    {
+        return Err(anyhow!(
+            "No version of seed {} matches {}. Available versions: {}",
+            seed_name.cyan(),
+            version_req.to_string().cyan(),
+            available_versions.join(", ")
+        ));
+    }
+
+    let silo_names = silos
+        .iter()
+        .map(|s| s.display_name())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Err(anyhow!(
+        "Seed {} is not found in the silo(s): {}",
+        seed_name.cyan(),
+        silo_names
+    ))
+}
+
+/// Returns all the versions of the seed with the given name, the latest one first
+pub fn seed_versions(seeds: Vec<Seed>, seed_name: &str) -> Vec<Seed> {
+    let mut versions = seeds
+        .into_iter()
+        .filter(|s| s.name == seed_name)
+        .collect::<Vec<_>>();
+    versions.sort_by(|a, b| b.version.cmp(&a.version));
+
+    versions
+}
+
+/// Bare repositories don't have a working tree so they are used as git silos
+fn is_bare_repository(path: &Path) -> bool {
+    path.join("HEAD").is_file() && path.join("objects").is_dir() && path.join("refs").is_dir()
+}
+
+fn warn_silo_skipped(silo: &ResolvedSilo, err: &Error) {
+    eprintln!(
+        "{}: Skipping silo {}: {}",
+        "Warning".yellow(),
+        silo.display_name().cyan(),
+        err.to_string().trim()
+    );
+}
+
+fn open_archive_silo(url: &str, format: ArchiveFormat, offline: bool) -> Result<TempDir, Error> {
+    if offline && is_remote(url) {
+        return Err(anyhow!(
+            "Archive silo {} cannot be downloaded in offline mode",
+            url.cyan()
+        ));
+    }
 
-pub fn fetch_silo_from_git(
-    git_url: impl AsRef<str>,
-    branch: impl AsRef<str>,
-) -> Result<Silo, Error> {
     let temp_dir = TempDir::new().context("Failed to create temporary directory")?;
-    let temp_path = temp_dir.path().join("repo");
+    unpack_archive(url, format, temp_dir.path())?;
+
+    Ok(temp_dir)
+}
+
+fn open_cached_silo(silo: &ResolvedSilo, options: &SiloOptions) -> Result<CacheEntry, Error> {
+    let offline = options.offline;
+    let urls = options.fetch_urls(silo);
+    let git_backend = options.git_backend.for_url(&urls[0]);
+    let mut entry = cache_entry(&silo.url, &silo.reference, git_backend)?;
+
+    if entry.repo_path().is_dir() {
+        if !offline && entry.backend != git_backend {
+            // The clones of different backends might not be compatible
+            entry.backend = git_backend;
+            clone_silo_to_cache(&mut entry, &urls, silo)?;
+        } else if offline || is_commit_sha(&silo.reference) {
+            // Use the cached copy as is, a commit never changes
+        } else if let Err(err) = update_cached_silo(&mut entry, &urls, silo) {
+            eprintln!(
+                "{}: Couldn't update the silo, using the cached copy: {}",
+                "Warning".yellow(),
+                err.to_string().trim()
+            );
+        }
+    } else if offline {
+        return Err(anyhow!(
+            "Silo {} ({}) is not available offline. Run the command once without \"--offline\" to cache it",
+            entry.url.cyan(),
+            entry.reference
+        ));
+    } else {
+        clone_silo_to_cache(&mut entry, &urls, silo)?;
+    }
+
+    Ok(entry)
+}
 
-    git_sparse_clone(git_url, branch, TOHUMRC_GLOB_PATTERN, &temp_path)?;
+/// Fetches the latest state of an already cached silo. The URLs are tried in order.
+pub fn update_cached_silo(
+    entry: &mut CacheEntry,
+    urls: &[String],
+    silo: &ResolvedSilo,
+) -> Result<(), Error> {
+    let repo_path = entry.repo_path();
 
-    let silo = read_silo(&temp_path)?;
-    Ok(silo)
+    let fetched_url = try_urls(&entry.url, urls, |url| {
+        entry
+            .backend
+            .backend(silo.token_for(url))
+            .sparse_update(&repo_path, url, &entry.reference)
+    })?;
+
+    let backend = entry.backend.backend(silo.token_for(&fetched_url));
+    checkout_silo_metadata(backend.as_ref(), &repo_path)?;
+    entry.commit = backend.head_commit(&repo_path)?;
+    entry.fetched_url = Some(fetched_url);
+    entry.touch()
 }
 
-pub fn read_silo(path: impl AsRef<Path>) -> Result<Vec<Seed>, Error> {
-    let mut silo: Silo = vec![];
+fn clone_silo_to_cache(
+    entry: &mut CacheEntry,
+    urls: &[String],
+    silo: &ResolvedSilo,
+) -> Result<(), Error> {
+    let repo_path = entry.repo_path();
+
+    let fetched_url = try_urls(&entry.url, urls, |url| {
+        // Start from scratch in case of a leftover from an interrupted clone or a failed mirror
+        remove_cache_entry(&entry.path)?;
 
-    let walker = WalkDir::new(&path)
+        let backend = entry.backend.backend(silo.token_for(url));
+        let result = backend
+            .sparse_clone(
+                url,
+                &entry.reference,
+                Path::new(SILO_INDEX_PATTERN),
+                &repo_path,
+            )
+            .and_then(|_| checkout_silo_metadata(backend.as_ref(), &repo_path));
+        if result.is_err() {
+            remove_cache_entry(&entry.path)?;
+        }
+
+        result
+    })?;
+
+    let backend = entry.backend.backend(silo.token_for(&fetched_url));
+    entry.commit = backend.head_commit(&repo_path)?;
+    entry.fetched_url = Some(fetched_url);
+    entry.touch()
+}
+
+/// Runs the fetch with each URL until one of them succeeds and returns that URL.
+/// If the silo is fetched from a mirror, the user is told which one it was.
+fn try_urls(
+    silo_url: &str,
+    urls: &[String],
+    mut fetch: impl FnMut(&str) -> Result<(), Error>,
+) -> Result<String, Error> {
+    if let [url] = urls {
+        return fetch(url).map(|_| url.clone());
+    }
+
+    let mut errors = vec![];
+    for (i, url) in urls.iter().enumerate() {
+        match fetch(url) {
+            Ok(()) => {
+                if i > 0 {
+                    eprintln!(
+                        "{}: Silo {} is fetched from mirror {}",
+                        "Note".cyan(),
+                        silo_url.cyan(),
+                        url.cyan()
+                    );
+                }
+                return Ok(url.clone());
+            }
+            Err(err) => errors.push(format!("{}: {}", url, err.to_string().trim())),
+        }
+    }
+
+    Err(anyhow!(
+        "Couldn't fetch the silo from any of its URLs\n{}",
+        errors.join("\n")
+    ))
+}
+
+/// Makes sure that the seed metadata and the silo metadata files are checked out. If the
+/// silo has an index, it is enough, otherwise all the `.tohumrc` files are checked out.
+fn checkout_silo_metadata(backend: &dyn GitBackend, repo_path: &Path) -> Result<(), Error> {
+    // Clones made before the index support only have the `.tohumrc` pattern
+    backend.sparse_add(repo_path, Path::new(SILO_INDEX_PATTERN), false)?;
+    backend.sparse_add(repo_path, Path::new(SILO_METADATA_PATTERN), false)?;
+    for pattern in SILO_MANIFEST_PATTERNS {
+        backend.sparse_add(repo_path, Path::new(pattern), false)?;
+    }
+
+    if !repo_path.join(SILO_INDEX_FILENAME).is_file() {
+        backend.sparse_add(repo_path, Path::new(TOHUMRC_GLOB_PATTERN), false)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the silo metadata and the seeds. The seeds are read from the silo index
+/// if there is one and `use_index` is set, otherwise the `.tohumrc` files are walked.
+pub fn read_silo(path: impl AsRef<Path>, use_index: bool) -> Result<Silo, Error> {
+    let index = if use_index {
+        SiloIndex::read(&path)?
+    } else {
+        None
+    };
+    let (seeds, diagnostics) = match index {
+        Some(index) => index.into_seeds(),
+        None => walk_silo(&path)?,
+    };
+
+    Ok(Silo {
+        metadata: read_silo_metadata(path),
+        seeds,
+        diagnostics,
+    })
+}
+
+/// Lists the diagnostics one per line
+pub fn format_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|d| format!("  {}", d))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// An invalid metadata file doesn't prevent the seeds to be used, so it is only warned
+fn read_silo_metadata(path: impl AsRef<Path>) -> Option<SiloMetadata> {
+    let metadata_path = path.as_ref().join(SILO_METADATA_FILENAME);
+    let file = File::open(&metadata_path).ok()?;
+
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(metadata) => Some(metadata),
+        Err(e) => {
+            eprintln!(
+                "{}: Invalid silo metadata {}: {}",
+                "Warning".yellow(),
+                metadata_path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Finds the seeds by walking all the `.tohumrc` files of the silo. The files that
+/// are not valid seeds are skipped and returned as diagnostics, with their paths
+/// relative to the silo root.
+pub fn walk_silo(path: impl AsRef<Path>) -> Result<(Vec<Seed>, Vec<Diagnostic>), Error> {
+    let path = path.as_ref();
+    let mut seeds: Vec<Seed> = vec![];
+    let mut diagnostics = vec![];
+
+    let walker = WalkDir::new(path)
         .into_iter()
         .filter_entry(|e| e.file_name().to_str().map(|s| s != ".git").unwrap_or(true))
         .filter_map(Result::ok);
@@ -42,33 +680,116 @@ pub fn read_silo(path: impl AsRef<Path>) -> Result<Vec<Seed>, Error> {
             continue;
         }
 
+        let relative_path = entry_path.strip_prefix(path)?;
         let file = File::open(entry_path)?;
         let reader = BufReader::new(file);
         let mut seed: Seed = match serde_json::from_reader(reader) {
             Ok(s) => s,
             Err(e) => {
-                eprintln!(
-                    "{}: Invalid seed {}: {}",
-                    "Warning".yellow(),
-                    entry_path.display(),
-                    e
-                );
+                diagnostics.push(Diagnostic::from_json_error(relative_path, &e));
                 continue;
             }
         };
 
+        if let Some(problem) = seed.problem() {
+            diagnostics.push(Diagnostic::new(relative_path, problem));
+            continue;
+        }
+
         // `.repo_path` points to the seed path inside the repo.
         // e.g `/tmp/temp-dir/repo-root/silo/seed` -> `silo/seed`
-        seed.repo_path = entry_path
-            .strip_prefix(&path)?
+        seed.repo_path = relative_path
             .parent()
             .unwrap()
             .to_str()
             .unwrap()
             .to_string();
 
-        silo.push(seed);
+        seeds.push(seed);
+    }
+
+    Ok((seeds, diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn fetch_urls_are_rewritten_and_unique() {
+        let config = Config {
+            url_rewrites: BTreeMap::from([(
+                "https://github.com/".to_string(),
+                "https://git.example.com/".to_string(),
+            )]),
+            ..Config::default()
+        };
+        let options = SiloOptions::new(&config, false, false);
+        let silo = ResolvedSilo {
+            name: None,
+            url: "https://github.com/acme/silo.git".to_string(),
+            reference: "main".to_string(),
+            token: Some("secret".to_string()),
+            mirrors: vec![
+                "https://mirror.example.com/silo.git".to_string(),
+                "https://git.example.com/acme/silo.git".to_string(),
+            ],
+        };
+
+        let urls = options.fetch_urls(&silo);
+        assert_eq!(
+            urls,
+            [
+                "https://git.example.com/acme/silo.git",
+                "https://mirror.example.com/silo.git"
+            ]
+        );
+        // None of them is the URL that the token is configured for
+        assert!(urls.iter().all(|url| silo.token_for(url).is_none()));
     }
 
-    Ok(silo)
+    #[test]
+    fn walk_silo_reports_invalid_seeds_with_location() {
+        let silo = TempDir::new().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = silo.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            "node/cli/.tohumrc",
+            r#"{"name": "@x/cli", "version": "1.0.0", "authors": [{"name": "A"}]}"#,
+        );
+        write(
+            "go/api/.tohumrc",
+            "{\n  \"name\": \"@x/api\"\n  \"version\": \"1.0.0\"\n}",
+        );
+        write(
+            "go/web/.tohumrc",
+            r#"{"name": "@x/web", "version": "1.0.0", "authors": []}"#,
+        );
+
+        let (seeds, mut diagnostics) = walk_silo(silo.path()).unwrap();
+        diagnostics.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(seeds.len(), 1);
+        assert_eq!(seeds[0].repo_path, "node/cli");
+
+        let api = &diagnostics[0];
+        assert_eq!(api.path, Path::new("go/api/.tohumrc"));
+        assert_eq!((api.line, api.column), (Some(3), Some(3)));
+        assert_eq!(api.message, "expected `,` or `}`");
+        assert_eq!(api.to_string(), "go/api/.tohumrc:3:3: expected `,` or `}`");
+
+        let web = &diagnostics[1];
+        assert_eq!(
+            web.to_string(),
+            "go/web/.tohumrc: Seed must include at least one author"
+        );
+        assert_eq!(diagnostics.len(), 2);
+    }
 }
@@ -0,0 +1,349 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::{Error, Result, anyhow};
+use serde_json::Value;
+use tera::{
+    Tera,
+    ast::{Expr, ExprVal, Node},
+};
+
+/// Parses the template into a Tera instance that has the filters of tohum
+pub fn new_template(name: &str, content: &str) -> tera::Result<Tera> {
+    let mut tera = Tera::default();
+    tera.register_filter("snake_case", snake_case);
+    tera.register_filter("kebab_case", kebab_case);
+    tera.add_raw_template(name, content)?;
+
+    Ok(tera)
+}
+
+/// Names of the variables that the template reads from the context. The
+/// variables that the template defines itself (e.g loop variables) are excluded.
+pub fn context_variables(tera: &Tera, name: &str) -> Result<BTreeSet<String>, Error> {
+    let mut variables = TemplateVariables::default();
+    variables.visit_nodes(&tera.get_template(name)?.ast);
+
+    Ok(variables
+        .used
+        .difference(&variables.defined)
+        .cloned()
+        .collect())
+}
+
+/// Orders the variables so each one comes after the variables that it depends on.
+/// Dependencies that are not in the map are assumed to be resolved already.
+pub fn dependency_order(
+    dependencies: &BTreeMap<String, BTreeSet<String>>,
+) -> Result<Vec<String>, Error> {
+    fn visit(
+        name: &str,
+        dependencies: &BTreeMap<String, BTreeSet<String>>,
+        path: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), Error> {
+        if order.iter().any(|n| n == name) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|n| n == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(anyhow!(
+                "Default values or conditions of the variables depend on each other: {}",
+                cycle.join(" -> ")
+            ));
+        }
+
+        path.push(name.to_string());
+        for dependency in &dependencies[name] {
+            if dependencies.contains_key(dependency) {
+                visit(dependency, dependencies, path, order)?;
+            }
+        }
+        path.pop();
+        order.push(name.to_string());
+
+        Ok(())
+    }
+
+    let mut order = vec![];
+    for name in dependencies.keys() {
+        visit(name, dependencies, &mut vec![], &mut order)?;
+    }
+
+    Ok(order)
+}
+
+/// Splits a text into lowercase words, e.g `My ProjectName` -> `my`, `project`, `name`
+fn words(value: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut previous: Option<char> = None;
+
+    for c in value.chars() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+        } else {
+            let is_boundary = c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase());
+            if is_boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.extend(c.to_lowercase());
+        }
+        previous = Some(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn snake_case(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let value = tera::try_get_value!("snake_case", "value", String, value);
+    Ok(Value::String(words(&value).join("_")))
+}
+
+fn kebab_case(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let value = tera::try_get_value!("kebab_case", "value", String, value);
+    Ok(Value::String(words(&value).join("-")))
+}
+
+/// Names of the variables that a template reads from the context
+/// and the ones that it defines itself (e.g loop variables)
+#[derive(Default)]
+struct TemplateVariables {
+    used: BTreeSet<String>,
+    defined: BTreeSet<String>,
+}
+
+impl TemplateVariables {
+    fn visit_nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.visit_node(node);
+        }
+    }
+
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::VariableBlock(_, expr) => self.visit_expr(expr),
+            Node::MacroDefinition(_, definition, _) => {
+                self.defined.extend(definition.args.keys().cloned());
+                for expr in definition.args.values().flatten() {
+                    self.visit_expr(expr);
+                }
+                self.visit_nodes(&definition.body);
+            }
+            Node::Set(_, set) => {
+                self.defined.insert(set.key.clone());
+                self.visit_expr(&set.value);
+            }
+            Node::FilterSection(_, section, _) => {
+                for expr in section.filter.args.values() {
+                    self.visit_expr(expr);
+                }
+                self.visit_nodes(&section.body);
+            }
+            Node::Block(_, block, _) => self.visit_nodes(&block.body),
+            Node::Forloop(_, forloop, _) => {
+                self.defined.insert(forloop.value.clone());
+                self.defined.insert("loop".to_string());
+                self.defined.extend(forloop.key.iter().cloned());
+                self.visit_expr(&forloop.container);
+                self.visit_nodes(&forloop.body);
+                if let Some(body) = &forloop.empty_body {
+                    self.visit_nodes(body);
+                }
+            }
+            Node::If(condition, _) => {
+                for (_, expr, body) in &condition.conditions {
+                    self.visit_expr(expr);
+                    self.visit_nodes(body);
+                }
+                if let Some((_, body)) = &condition.otherwise {
+                    self.visit_nodes(body);
+                }
+            }
+            Node::Super
+            | Node::Text(_)
+            | Node::Extends(..)
+            | Node::Include(..)
+            | Node::ImportMacro(..)
+            | Node::Raw(..)
+            | Node::Break(_)
+            | Node::Continue(_)
+            | Node::Comment(..) => {}
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.visit_expr_val(&expr.val);
+        for filter in &expr.filters {
+            for arg in filter.args.values() {
+                self.visit_expr(arg);
+            }
+        }
+    }
+
+    fn visit_expr_val(&mut self, val: &ExprVal) {
+        match val {
+            ExprVal::Ident(ident) => self.use_ident(ident),
+            ExprVal::Math(math) => {
+                self.visit_expr(&math.lhs);
+                self.visit_expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.visit_expr(&logic.lhs);
+                self.visit_expr(&logic.rhs);
+            }
+            ExprVal::Test(test) => {
+                self.use_ident(&test.ident);
+                for arg in &test.args {
+                    self.visit_expr(arg);
+                }
+            }
+            ExprVal::MacroCall(call) => {
+                for arg in call.args.values() {
+                    self.visit_expr(arg);
+                }
+            }
+            ExprVal::FunctionCall(call) => {
+                for arg in call.args.values() {
+                    self.visit_expr(arg);
+                }
+            }
+            ExprVal::Array(items) => {
+                for item in items {
+                    self.visit_expr(item);
+                }
+            }
+            ExprVal::StringConcat(concat) => {
+                for value in &concat.values {
+                    self.visit_expr_val(value);
+                }
+            }
+            ExprVal::In(expr) => {
+                self.visit_expr(&expr.lhs);
+                self.visit_expr(&expr.rhs);
+            }
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+        }
+    }
+
+    /// An identifier can be a path like `silo.url` or `items[index]`. The root of
+    /// the path and the identifiers inside the brackets are the used variables.
+    fn use_ident(&mut self, ident: &str) {
+        let root_end = ident.find(['.', '[']).unwrap_or(ident.len());
+        self.used.insert(ident[..root_end].to_string());
+
+        for part in ident.split('[').skip(1) {
+            let index = part.split(']').next().unwrap_or_default().trim();
+            let is_literal = index.is_empty()
+                || index.starts_with(['"', '\'', '`'])
+                || index.chars().all(|c| c.is_ascii_digit());
+            if !is_literal {
+                self.use_ident(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies(items: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        items
+            .iter()
+            .map(|(name, deps)| {
+                (
+                    name.to_string(),
+                    deps.iter().map(|d| d.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn render(content: &str) -> String {
+        let mut context = tera::Context::new();
+        context.insert("project_name", "My CoolProject-name");
+        new_template("test", content)
+            .unwrap()
+            .render("test", &context)
+            .unwrap()
+    }
+
+    fn variables(content: &str) -> Vec<String> {
+        let tera = new_template("test", content).unwrap();
+        context_variables(&tera, "test")
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn orders_dependencies_first() {
+        let order = dependency_order(&dependencies(&[
+            ("a", &["c"]),
+            ("b", &["a", "project_name"]),
+            ("c", &[]),
+        ]))
+        .unwrap();
+
+        assert_eq!(order, ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn reports_dependency_cycle() {
+        let err = dependency_order(&dependencies(&[
+            ("a", &["b"]),
+            ("b", &["c"]),
+            ("c", &["a"]),
+        ]))
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Default values or conditions of the variables depend on each other: a -> b -> c -> a"
+        );
+
+        let err = dependency_order(&dependencies(&[("a", &["a"])])).unwrap_err();
+        assert!(err.to_string().ends_with(": a -> a"), "{}", err);
+    }
+
+    #[test]
+    fn converts_case() {
+        assert_eq!(
+            render("{{ project_name | snake_case }}"),
+            "my_cool_project_name"
+        );
+        assert_eq!(
+            render("{{ project_name | kebab_case }}"),
+            "my-cool-project-name"
+        );
+    }
+
+    #[test]
+    fn finds_context_variables() {
+        assert_eq!(
+            variables("{{ org }}/{{ project_name | kebab_case }}"),
+            ["org", "project_name"]
+        );
+        assert_eq!(
+            variables("{% if enable_grpc and grpc_port > 2000 %}{{ silo.url }}{% endif %}"),
+            ["enable_grpc", "grpc_port", "silo"]
+        );
+        // Loop and set variables are defined by the template itself
+        assert_eq!(
+            variables(
+                "{% set x = prefix %}{% for item in items %}{{ x }}{{ item }}{{ loop.index }}{% endfor %}"
+            ),
+            ["items", "prefix"]
+        );
+        assert_eq!(
+            variables("{{ values[key] | default(value=fallback) }}"),
+            ["fallback", "key", "values"]
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use anyhow::{Context, Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::constants::VENDOR_MANIFEST_FILENAME;
+
+/// Records where a vendored silo snapshot comes from. It is written to
+/// the root of the snapshot which can be used as a silo via `--silo`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VendorManifest {
+    pub url: String,
+    #[serde(rename = "ref")]
+    pub reference: String,
+    /// SHA of the commit that the snapshot is taken from. Local and archive silos don't have one.
+    pub commit: Option<String>,
+    /// Unix timestamp (in seconds) of when the snapshot is taken
+    pub vendored_at: u64,
+    pub seeds: Vec<VendoredSeed>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VendoredSeed {
+    pub name: String,
+    pub version: semver::Version,
+    /// Path of the seed directory, relative to the snapshot root
+    pub path: String,
+}
+
+impl VendorManifest {
+    /// Reads the vendor manifest of a snapshot. Returns `None` if the directory is not a snapshot.
+    pub fn read(dir: impl AsRef<Path>) -> Result<Option<VendorManifest>, Error> {
+        let path = dir.as_ref().join(VENDOR_MANIFEST_FILENAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let reader = BufReader::new(File::open(&path)?);
+        let manifest = serde_json::from_reader(reader)
+            .with_context(|| format!("Invalid vendor manifest {}", path.display()))?;
+
+        Ok(Some(manifest))
+    }
+
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<(), Error> {
+        let path = dir.as_ref().join(VENDOR_MANIFEST_FILENAME);
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to write vendor manifest {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self)?;
+
+        Ok(())
+    }
+}